@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+
+use actix_web::{FromRequest, HttpRequest, cookie::{Cookie, CookieBuilder}, dev::Payload, web};
+use deadpool_redis::Pool as RedisPool;
+use futures_util::future::LocalBoxFuture;
+
+use crate::{config::{CookieConfig, get_config}, db::{Admin, AdminRole, Voter}, error::{AppError, AppResult}, util::{verify_admin_token, verify_voter_token}};
+
+pub const VOTER_TOKEN_COOKIE: &str = "voter_token";
+pub const ADMIN_SESSION_COOKIE: &str = "admin_session_token";
+
+/// A voter verified from the `voter_token` cookie.
+pub struct AuthenticatedVoter(pub Voter);
+
+/// An admin verified from the `admin_session_token` cookie.
+pub struct AuthenticatedAdmin {
+      pub admin: Admin,
+      // The token of the session the request was sent with
+      pub session_token: String
+}
+
+/// Routes that both voters and admins can see.
+pub enum VoterOrAdmin {
+      Voter(Voter),
+      Admin(Admin)
+}
+
+impl AuthenticatedAdmin {
+      /// Fails with `Forbidden` when the admin doesn't have the role.
+      pub fn require_role(self, role: AdminRole) -> AppResult<Admin> {
+            if !self.admin.has_role(role) {
+                  return Err(AppError::Forbidden);
+            }
+
+            Ok(self.admin)
+      }
+}
+
+/// An HTTP only cookie with the configured `Secure`, `SameSite` and `Domain` attributes.
+pub fn build_cookie<'c>(name: &'c str, value: impl Into<Cow<'c, str>>) -> CookieBuilder<'c> {
+      let cookie_config: &CookieConfig = &get_config().cookie;
+      let cookie_builder: CookieBuilder<'c> = Cookie::build(name, value)
+            .path("/")
+            .secure(cookie_config.secure)
+            .same_site(cookie_config.same_site)
+            .http_only(true);
+
+      match &cookie_config.domain {
+            Some(domain) => cookie_builder.domain(domain.clone()),
+            None => cookie_builder
+      }
+}
+
+fn get_cookie_value(req: &HttpRequest, cookie_name: &str) -> Option<String> {
+      req.cookie(cookie_name).map(|cookie| cookie.value().to_string())
+}
+
+fn get_redis_pool(req: &HttpRequest) -> Option<web::Data<RedisPool>> {
+      req.app_data::<web::Data<RedisPool>>().cloned()
+}
+
+fn require_redis_pool(redis_pool: Option<web::Data<RedisPool>>) -> AppResult<web::Data<RedisPool>> {
+      redis_pool.ok_or_else(|| AppError::Internal(String::from("The redis pool isn't registered as app data.")))
+}
+
+async fn authenticate_voter(cookie_voter_token: Option<String>, redis_pool: Option<web::Data<RedisPool>>) -> AppResult<Voter> {
+      let cookie_voter_token: String = cookie_voter_token.ok_or(AppError::Unauthorized)?;
+
+      let redis_pool: web::Data<RedisPool> = require_redis_pool(redis_pool)?;
+      verify_voter_token(cookie_voter_token.as_str(), &redis_pool).await
+}
+
+async fn authenticate_admin(cookie_admin_token: Option<String>, redis_pool: Option<web::Data<RedisPool>>) -> AppResult<AuthenticatedAdmin> {
+      let cookie_admin_token: String = cookie_admin_token.ok_or(AppError::Unauthorized)?;
+
+      let redis_pool: web::Data<RedisPool> = require_redis_pool(redis_pool)?;
+      let admin: Admin = verify_admin_token(cookie_admin_token.as_str(), &redis_pool).await?;
+
+      Ok(AuthenticatedAdmin { admin, session_token: cookie_admin_token })
+}
+
+impl FromRequest for AuthenticatedVoter {
+      type Error = AppError;
+      type Future = LocalBoxFuture<'static, AppResult<Self>>;
+
+      fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            let cookie_voter_token: Option<String> = get_cookie_value(req, VOTER_TOKEN_COOKIE);
+            let redis_pool = get_redis_pool(req);
+
+            Box::pin(async move {
+                  authenticate_voter(cookie_voter_token, redis_pool).await.map(AuthenticatedVoter)
+            })
+      }
+}
+
+impl FromRequest for AuthenticatedAdmin {
+      type Error = AppError;
+      type Future = LocalBoxFuture<'static, AppResult<Self>>;
+
+      fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            let cookie_admin_token: Option<String> = get_cookie_value(req, ADMIN_SESSION_COOKIE);
+            let redis_pool = get_redis_pool(req);
+
+            Box::pin(authenticate_admin(cookie_admin_token, redis_pool))
+      }
+}
+
+impl FromRequest for VoterOrAdmin {
+      type Error = AppError;
+      type Future = LocalBoxFuture<'static, AppResult<Self>>;
+
+      fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+            let cookie_admin_token: Option<String> = get_cookie_value(req, ADMIN_SESSION_COOKIE);
+            let cookie_voter_token: Option<String> = get_cookie_value(req, VOTER_TOKEN_COOKIE);
+            let redis_pool = get_redis_pool(req);
+
+            Box::pin(async move {
+                  // Prefer the admin session when both of the cookies are sent
+                  if cookie_admin_token.is_some()
+                        && let Ok(authenticated_admin) = authenticate_admin(cookie_admin_token, redis_pool.clone()).await {
+                        return Ok(VoterOrAdmin::Admin(authenticated_admin.admin));
+                  }
+
+                  authenticate_voter(cookie_voter_token, redis_pool).await.map(VoterOrAdmin::Voter)
+            })
+      }
+}
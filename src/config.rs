@@ -0,0 +1,593 @@
+use std::{fmt, net::{IpAddr, SocketAddr}, path::Path};
+
+use actix_web::cookie::SameSite;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use time::{UtcOffset, macros::offset};
+
+#[cfg(test)]
+mod tests;
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+static DEFAULT_CONFIG_FILE: &str = "config.toml";
+static DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8080";
+static DEFAULT_REDIS_POOL_SIZE: usize = 16;
+static DEFAULT_ADMIN_SESSION_TOKEN_LENGTH: usize = 50;
+static MIN_ADMIN_SESSION_TOKEN_LENGTH: usize = 32;
+// Letters without the easily confused I, O, l and o
+static DEFAULT_VOTER_TOKEN_ALPHABET: &str = "ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnpqrstuvwxyz";
+static DEFAULT_VOTER_TOKEN_LENGTH: usize = 6;
+static DEFAULT_TIMEZONE: UtcOffset = offset!(+7);
+// The replica leading the background jobs is replaced after this many seconds without renewing its lock
+static DEFAULT_LEADER_LOCK_TIMEOUT: usize = 10;
+// The lock is renewed three times within its timeout
+static MIN_LEADER_LOCK_TIMEOUT: usize = 3;
+static MIN_RECEIPT_SECRET_LENGTH: usize = 32;
+static DEFAULT_ADMIN_SESSION_IDLE_TIMEOUT: usize = 60 * 60;
+static DEFAULT_ADMIN_SESSION_ABSOLUTE_TIMEOUT: usize = 12 * 60 * 60;
+static DEFAULT_LOGIN_ATTEMPT_LIMIT: usize = 5;
+static DEFAULT_LOGIN_LOCKOUT_BASE: usize = 30;
+static DEFAULT_LOGIN_LOCKOUT_MAX: usize = 60 * 60;
+static DEFAULT_LOGIN_FAILURE_WINDOW: usize = 24 * 60 * 60;
+
+pub struct Config {
+      pub server: ServerConfig,
+      pub database: DatabaseConfig,
+      pub redis: RedisConfig,
+      pub cookie: CookieConfig,
+      pub token: TokenConfig,
+      pub session: SessionConfig,
+      pub login: LoginLimitConfig,
+      pub election: ElectionConfig,
+      // Used for the log timestamps
+      pub timezone: UtcOffset
+}
+
+pub struct ServerConfig {
+      pub bind_address: SocketAddr,
+      // Number of the HTTP workers, actix uses one per CPU core when it's not set
+      pub workers: Option<usize>,
+      // Empty allows no cross-origin request, `*` allows any origin
+      pub cors_allowed_origins: Vec<String>,
+      // Only these peers may set the client IP through `X-Forwarded-For`
+      pub trusted_proxies: Vec<IpAddr>
+}
+
+pub struct DatabaseConfig {
+      pub url: String,
+      pub username: String,
+      pub password: String,
+      pub namespace: String,
+      pub name: String
+}
+
+pub struct RedisConfig {
+      pub url: String,
+      pub pool_size: usize,
+      // Seconds
+      pub leader_lock_timeout: i64
+}
+
+pub struct CookieConfig {
+      pub secure: bool,
+      pub same_site: SameSite,
+      pub domain: Option<String>
+}
+
+pub struct TokenConfig {
+      pub admin_session_length: usize,
+      pub voter_length: usize,
+      // Sorted and without duplicates
+      pub voter_alphabet: Vec<char>,
+      pub voter_checksum: bool
+}
+
+/// Timeouts of the admin sessions in seconds.
+pub struct SessionConfig {
+      // Session is revoked after this long without any request
+      pub idle_timeout: i64,
+      // Session is revoked after this long since login no matter the activity
+      pub absolute_timeout: i64
+}
+
+/// Lockout of the failed logins, the durations are in seconds.
+pub struct LoginLimitConfig {
+      // Failed login attempts allowed before the first lockout
+      pub attempt_limit: i64,
+      // The first lockout duration, doubled for each failure after it
+      pub lockout_base: i64,
+      pub lockout_max: i64,
+      // Failed login attempts are forgotten after this long without another failure
+      pub failure_window: i64
+}
+
+pub struct ElectionConfig {
+      // Votes are stored as a participation and an anonymous ballot
+      pub secret_ballot: bool,
+      // Tallies are hidden until the election of the campus is closed
+      pub results_embargo: bool,
+      // Vote receipts are disabled when it's not set
+      pub receipt_secret: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+      server: FileServerConfig,
+      database: FileDatabaseConfig,
+      redis: FileRedisConfig,
+      cookie: FileCookieConfig,
+      token: FileTokenConfig,
+      session: FileSessionConfig,
+      login: FileLoginLimitConfig,
+      election: FileElectionConfig,
+      timezone: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileServerConfig {
+      bind_address: Option<String>,
+      workers: Option<usize>,
+      cors_allowed_origins: Option<Vec<String>>,
+      trusted_proxies: Option<Vec<String>>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileDatabaseConfig {
+      url: Option<String>,
+      username: Option<String>,
+      password: Option<String>,
+      namespace: Option<String>,
+      name: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileRedisConfig {
+      url: Option<String>,
+      pool_size: Option<usize>,
+      leader_lock_timeout: Option<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileCookieConfig {
+      secure: Option<bool>,
+      same_site: Option<String>,
+      domain: Option<String>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileTokenConfig {
+      admin_session_length: Option<usize>,
+      voter_length: Option<usize>,
+      voter_alphabet: Option<String>,
+      voter_checksum: Option<bool>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileSessionConfig {
+      idle_timeout: Option<usize>,
+      absolute_timeout: Option<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileLoginLimitConfig {
+      attempt_limit: Option<usize>,
+      lockout_base: Option<usize>,
+      lockout_max: Option<usize>,
+      failure_window: Option<usize>
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct FileElectionConfig {
+      secret_ballot: Option<bool>,
+      results_embargo: Option<bool>,
+      receipt_secret: Option<String>
+}
+
+/// A setting with its environment variable and its key in the config file.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKey {
+      pub env: &'static str,
+      pub file: &'static str
+}
+
+const fn key(env: &'static str, file: &'static str) -> ConfigKey {
+      ConfigKey { env, file }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+      File(String, String),
+      Missing(ConfigKey),
+      Invalid(ConfigKey, String)
+}
+
+impl fmt::Display for ConfigError {
+      fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                  ConfigError::File(path, reason) => write!(f, "The config file {} can't be read. Error: {}", path, reason),
+                  ConfigError::Missing(key) => write!(f, "{} (`{}` in the config file) is required.", key.env, key.file),
+                  ConfigError::Invalid(key, reason) => write!(f, "{} (`{}` in the config file) is invalid, {}.", key.env, key.file, reason)
+            }
+      }
+}
+
+/// Collects every problem so they're all reported at once.
+#[derive(Default)]
+struct ConfigLoader {
+      errors: Vec<ConfigError>
+}
+
+impl ConfigLoader {
+      /// The environment variable takes precedence over the config file.
+      fn read<T>(&mut self, key: ConfigKey, file_value: Option<T>, parse: fn(&str) -> Result<T, String>) -> Option<T> {
+            let env_value: Option<String> = std::env::var(key.env)
+                  .ok()
+                  .filter(|data| !data.trim().is_empty());
+
+            match env_value {
+                  Some(data) => match parse(data.trim()) {
+                        Ok(value) => Some(value),
+                        Err(reason) => {
+                              self.errors.push(ConfigError::Invalid(key, reason));
+                              None
+                        }
+                  },
+                  None => file_value
+            }
+      }
+
+      /// Like `read` but the value of the config file is parsed too.
+      fn read_parsed<T>(&mut self, key: ConfigKey, file_value: Option<String>, parse: fn(&str) -> Result<T, String>) -> Option<T> {
+            let value: String = self.read(key, file_value, parse_string)?;
+
+            self.check(key, parse(value.trim()))
+      }
+
+      fn require<T>(&mut self, key: ConfigKey, value: Option<T>) -> Option<T> {
+            if value.is_none() {
+                  self.errors.push(ConfigError::Missing(key));
+            }
+
+            value
+      }
+
+      fn check<T>(&mut self, key: ConfigKey, result: Result<T, String>) -> Option<T> {
+            match result {
+                  Ok(value) => Some(value),
+                  Err(reason) => {
+                        self.errors.push(ConfigError::Invalid(key, reason));
+                        None
+                  }
+            }
+      }
+}
+
+fn parse_string(data: &str) -> Result<String, String> {
+      Ok(data.to_string())
+}
+
+fn parse_bool(data: &str) -> Result<bool, String> {
+      match data {
+            "true" | "1" => Ok(true),
+            "false" | "0" => Ok(false),
+            _ => Err(String::from("expected true or false"))
+      }
+}
+
+fn parse_usize(data: &str) -> Result<usize, String> {
+      data.parse::<usize>().map_err(|_| String::from("expected a positive number"))
+}
+
+fn parse_list(data: &str) -> Result<Vec<String>, String> {
+      Ok(data.split(',').map(|data| data.trim().to_string()).filter(|data| !data.is_empty()).collect())
+}
+
+fn parse_ip(data: &str) -> Result<IpAddr, String> {
+      data.parse::<IpAddr>().map_err(|_| format!("{} isn't an IP address", data))
+}
+
+fn parse_socket_address(data: &str) -> Result<SocketAddr, String> {
+      data.parse::<SocketAddr>().map_err(|_| String::from("expected an IP address and a port such as 127.0.0.1:8080"))
+}
+
+fn parse_same_site(data: &str) -> Result<SameSite, String> {
+      match data.to_lowercase().as_str() {
+            "strict" => Ok(SameSite::Strict),
+            "lax" => Ok(SameSite::Lax),
+            "none" => Ok(SameSite::None),
+            _ => Err(String::from("expected strict, lax or none"))
+      }
+}
+
+/// `UTC`, `+7`, `+07:00` or `-03:30`.
+fn parse_timezone(data: &str) -> Result<UtcOffset, String> {
+      let invalid_timezone = || String::from("expected UTC or an offset such as +07:00");
+      if data.eq_ignore_ascii_case("utc") || data == "Z" {
+            return Ok(UtcOffset::UTC);
+      }
+
+      let (sign, offset): (i8, &str) = match data.split_at_checked(1) {
+            Some(("+", offset)) => (1, offset),
+            Some(("-", offset)) => (-1, offset),
+            _ => return Err(invalid_timezone())
+      };
+      let (hours, minutes): (&str, &str) = offset.split_once(':').unwrap_or((offset, "0"));
+      let hours: i8 = hours.parse::<i8>().map_err(|_| invalid_timezone())?;
+      let minutes: i8 = minutes.parse::<i8>().map_err(|_| invalid_timezone())?;
+      if !(0..=14).contains(&hours) || !(0..=59).contains(&minutes) {
+            return Err(invalid_timezone());
+      }
+
+      UtcOffset::from_hms(sign * hours, sign * minutes, 0).map_err(|_| invalid_timezone())
+}
+
+fn validate_positive(value: usize) -> Result<usize, String> {
+      if value == 0 {
+            return Err(String::from("it must be more than 0"));
+      }
+
+      Ok(value)
+}
+
+fn validate_admin_session_length(value: usize) -> Result<usize, String> {
+      if value < MIN_ADMIN_SESSION_TOKEN_LENGTH {
+            return Err(format!("it must be at least {}", MIN_ADMIN_SESSION_TOKEN_LENGTH));
+      }
+
+      Ok(value)
+}
+
+fn validate_minimum(value: usize, minimum: usize) -> Result<usize, String> {
+      if value < minimum {
+            return Err(format!("it must be at least {}", minimum));
+      }
+
+      Ok(value)
+}
+
+fn validate_receipt_secret(secret: String) -> Result<String, String> {
+      if secret.chars().count() < MIN_RECEIPT_SECRET_LENGTH {
+            return Err(format!("it must be at least {} characters", MIN_RECEIPT_SECRET_LENGTH));
+      }
+
+      Ok(secret)
+}
+
+// A tally broadcast right after each ballot would tell which candidate the voter that just logged in picked
+fn validate_results_embargo(results_embargo: bool, secret_ballot: bool) -> Result<bool, String> {
+      if secret_ballot && !results_embargo {
+            return Err(String::from("it must be enabled in secret ballot mode"));
+      }
+
+      Ok(results_embargo)
+}
+
+fn validate_origins(origins: Vec<String>) -> Result<Vec<String>, String> {
+      if origins.iter().any(|origin| origin == "*") {
+            if origins.len() > 1 {
+                  return Err(String::from("`*` can't be combined with other origins"));
+            }
+
+            return Ok(origins);
+      }
+
+      for origin in &origins {
+            if !(origin.starts_with("http://") || origin.starts_with("https://")) || origin.ends_with('/') {
+                  return Err(format!("{} isn't an origin such as https://example.com", origin));
+            }
+      }
+
+      Ok(origins)
+}
+
+fn validate_voter_alphabet(alphabet: String) -> Result<Vec<char>, String> {
+      let mut alphabet: Vec<char> = alphabet.chars().collect();
+      alphabet.sort_unstable();
+      alphabet.dedup();
+
+      if alphabet.len() < 2 {
+            return Err(String::from("it needs at least 2 different characters"));
+      }
+      if alphabet.iter().any(|character| character.is_whitespace()) {
+            return Err(String::from("it can't contain whitespace"));
+      }
+
+      Ok(alphabet)
+}
+
+fn read_config_file() -> Result<FileConfig, ConfigError> {
+      // The default config file is optional, but one that is set explicitly must exist
+      let config_file: Option<String> = std::env::var("CONFIG_FILE").ok().filter(|data| !data.is_empty());
+      let path: String = match config_file {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => DEFAULT_CONFIG_FILE.to_string(),
+            None => return Ok(FileConfig::default())
+      };
+
+      let content: String = std::fs::read_to_string(&path)
+            .map_err(|err| ConfigError::File(path.clone(), err.to_string()))?;
+
+      toml::from_str::<FileConfig>(content.as_str())
+            .map_err(|err| ConfigError::File(path.clone(), err.to_string()))
+}
+
+fn load_config() -> Result<Config, Vec<ConfigError>> {
+      let file_config: FileConfig = read_config_file().map_err(|err| vec![err])?;
+      let mut loader: ConfigLoader = ConfigLoader::default();
+
+      // Server
+      let bind_address_key: ConfigKey = key("BIND_ADDRESS", "server.bind_address");
+      let bind_address: Option<SocketAddr> = loader.read_parsed(bind_address_key, file_config.server.bind_address.or(Some(DEFAULT_BIND_ADDRESS.to_string())), parse_socket_address);
+
+      let workers_key: ConfigKey = key("HTTP_WORKERS", "server.workers");
+      let workers: Option<usize> = loader.read(workers_key, file_config.server.workers, parse_usize);
+      let workers: Option<usize> = workers.and_then(|data| loader.check(workers_key, validate_positive(data)));
+
+      let cors_allowed_origins_key: ConfigKey = key("CORS_ALLOWED_ORIGINS", "server.cors_allowed_origins");
+      let cors_allowed_origins: Vec<String> = loader.read(cors_allowed_origins_key, file_config.server.cors_allowed_origins, parse_list).unwrap_or_default();
+      let cors_allowed_origins: Option<Vec<String>> = loader.check(cors_allowed_origins_key, validate_origins(cors_allowed_origins));
+
+      let trusted_proxies_key: ConfigKey = key("TRUSTED_PROXIES", "server.trusted_proxies");
+      let trusted_proxies: Vec<String> = loader.read(trusted_proxies_key, file_config.server.trusted_proxies, parse_list).unwrap_or_default();
+      let trusted_proxies: Vec<IpAddr> = loader.check(trusted_proxies_key, trusted_proxies.iter().map(|data| parse_ip(data)).collect()).unwrap_or_default();
+
+      // Database
+      let database_url_key: ConfigKey = key("DATABASE_URL", "database.url");
+      let database_url: Option<String> = loader.read(database_url_key, file_config.database.url, parse_string);
+      let database_url: Option<String> = loader.require(database_url_key, database_url);
+
+      let database_username_key: ConfigKey = key("SURREAL_USERNAME", "database.username");
+      let database_username: Option<String> = loader.read(database_username_key, file_config.database.username, parse_string);
+      let database_username: Option<String> = loader.require(database_username_key, database_username);
+
+      let database_password_key: ConfigKey = key("SURREAL_PASSWORD", "database.password");
+      let database_password: Option<String> = loader.read(database_password_key, file_config.database.password, parse_string);
+      let database_password: Option<String> = loader.require(database_password_key, database_password);
+
+      let database_namespace_key: ConfigKey = key("SURREAL_NS_NAME", "database.namespace");
+      let database_namespace: Option<String> = loader.read(database_namespace_key, file_config.database.namespace, parse_string);
+      let database_namespace: Option<String> = loader.require(database_namespace_key, database_namespace);
+
+      let database_name_key: ConfigKey = key("SURREAL_DB_NAME", "database.name");
+      let database_name: Option<String> = loader.read(database_name_key, file_config.database.name, parse_string);
+      let database_name: Option<String> = loader.require(database_name_key, database_name);
+
+      // Redis
+      let redis_url_key: ConfigKey = key("REDIS_URL", "redis.url");
+      let redis_url: Option<String> = loader.read(redis_url_key, file_config.redis.url, parse_string);
+      let redis_url: Option<String> = loader.require(redis_url_key, redis_url);
+      let redis_url: Option<String> = redis_url.and_then(|data| {
+            let result = redis::parse_redis_url(data.as_str()).map(|_| data).ok_or(String::from("expected a URL such as redis://127.0.0.1:6379"));
+            loader.check(redis_url_key, result)
+      });
+
+      let redis_pool_size_key: ConfigKey = key("REDIS_POOL_SIZE", "redis.pool_size");
+      let redis_pool_size: usize = loader.read(redis_pool_size_key, file_config.redis.pool_size, parse_usize).unwrap_or(DEFAULT_REDIS_POOL_SIZE);
+      let redis_pool_size: Option<usize> = loader.check(redis_pool_size_key, validate_positive(redis_pool_size));
+
+      let leader_lock_timeout_key: ConfigKey = key("LEADER_LOCK_TIMEOUT", "redis.leader_lock_timeout");
+      let leader_lock_timeout: usize = loader.read(leader_lock_timeout_key, file_config.redis.leader_lock_timeout, parse_usize).unwrap_or(DEFAULT_LEADER_LOCK_TIMEOUT);
+      let leader_lock_timeout: usize = loader.check(leader_lock_timeout_key, validate_minimum(leader_lock_timeout, MIN_LEADER_LOCK_TIMEOUT)).unwrap_or(DEFAULT_LEADER_LOCK_TIMEOUT);
+
+      // Cookie
+      let cookie_secure_key: ConfigKey = key("COOKIE_SECURE", "cookie.secure");
+      let cookie_secure: bool = loader.read(cookie_secure_key, file_config.cookie.secure, parse_bool).unwrap_or(true);
+
+      let cookie_same_site_key: ConfigKey = key("COOKIE_SAME_SITE", "cookie.same_site");
+      let cookie_same_site: SameSite = loader.read_parsed(cookie_same_site_key, file_config.cookie.same_site, parse_same_site).unwrap_or(SameSite::Lax);
+      if cookie_same_site == SameSite::None && !cookie_secure {
+            loader.errors.push(ConfigError::Invalid(cookie_same_site_key, String::from("browsers only accept none on secure cookies")));
+      }
+
+      let cookie_domain_key: ConfigKey = key("COOKIE_DOMAIN", "cookie.domain");
+      let cookie_domain: Option<String> = loader.read(cookie_domain_key, file_config.cookie.domain, parse_string);
+
+      // Token
+      let admin_session_length_key: ConfigKey = key("ADMIN_SESSION_TOKEN_LENGTH", "token.admin_session_length");
+      let admin_session_length: usize = loader.read(admin_session_length_key, file_config.token.admin_session_length, parse_usize).unwrap_or(DEFAULT_ADMIN_SESSION_TOKEN_LENGTH);
+      let admin_session_length: Option<usize> = loader.check(admin_session_length_key, validate_admin_session_length(admin_session_length));
+
+      let voter_length_key: ConfigKey = key("VOTER_TOKEN_LENGTH", "token.voter_length");
+      let voter_length: usize = loader.read(voter_length_key, file_config.token.voter_length, parse_usize).unwrap_or(DEFAULT_VOTER_TOKEN_LENGTH);
+      let voter_length: Option<usize> = loader.check(voter_length_key, validate_positive(voter_length));
+
+      let voter_alphabet_key: ConfigKey = key("VOTER_TOKEN_ALPHABET", "token.voter_alphabet");
+      let voter_alphabet: String = loader.read(voter_alphabet_key, file_config.token.voter_alphabet, parse_string).unwrap_or(DEFAULT_VOTER_TOKEN_ALPHABET.to_string());
+      let voter_alphabet: Option<Vec<char>> = loader.check(voter_alphabet_key, validate_voter_alphabet(voter_alphabet));
+
+      let voter_checksum_key: ConfigKey = key("VOTER_TOKEN_CHECKSUM", "token.voter_checksum");
+      let voter_checksum: bool = loader.read(voter_checksum_key, file_config.token.voter_checksum, parse_bool).unwrap_or(false);
+
+      // Session
+      let session_idle_timeout_key: ConfigKey = key("ADMIN_SESSION_IDLE_TIMEOUT", "session.idle_timeout");
+      let session_idle_timeout: usize = loader.read(session_idle_timeout_key, file_config.session.idle_timeout, parse_usize).unwrap_or(DEFAULT_ADMIN_SESSION_IDLE_TIMEOUT);
+      let session_idle_timeout: usize = loader.check(session_idle_timeout_key, validate_positive(session_idle_timeout)).unwrap_or(DEFAULT_ADMIN_SESSION_IDLE_TIMEOUT);
+
+      let session_absolute_timeout_key: ConfigKey = key("ADMIN_SESSION_ABSOLUTE_TIMEOUT", "session.absolute_timeout");
+      let session_absolute_timeout: usize = loader.read(session_absolute_timeout_key, file_config.session.absolute_timeout, parse_usize).unwrap_or(DEFAULT_ADMIN_SESSION_ABSOLUTE_TIMEOUT);
+      let session_absolute_timeout: usize = loader.check(session_absolute_timeout_key, validate_minimum(session_absolute_timeout, session_idle_timeout)).unwrap_or(DEFAULT_ADMIN_SESSION_ABSOLUTE_TIMEOUT);
+
+      // Login limit
+      let login_attempt_limit_key: ConfigKey = key("LOGIN_ATTEMPT_LIMIT", "login.attempt_limit");
+      let login_attempt_limit: usize = loader.read(login_attempt_limit_key, file_config.login.attempt_limit, parse_usize).unwrap_or(DEFAULT_LOGIN_ATTEMPT_LIMIT);
+      let login_attempt_limit: usize = loader.check(login_attempt_limit_key, validate_positive(login_attempt_limit)).unwrap_or(DEFAULT_LOGIN_ATTEMPT_LIMIT);
+
+      let login_lockout_base_key: ConfigKey = key("LOGIN_LOCKOUT_BASE", "login.lockout_base");
+      let login_lockout_base: usize = loader.read(login_lockout_base_key, file_config.login.lockout_base, parse_usize).unwrap_or(DEFAULT_LOGIN_LOCKOUT_BASE);
+      let login_lockout_base: usize = loader.check(login_lockout_base_key, validate_positive(login_lockout_base)).unwrap_or(DEFAULT_LOGIN_LOCKOUT_BASE);
+
+      let login_lockout_max_key: ConfigKey = key("LOGIN_LOCKOUT_MAX", "login.lockout_max");
+      let login_lockout_max: usize = loader.read(login_lockout_max_key, file_config.login.lockout_max, parse_usize).unwrap_or(DEFAULT_LOGIN_LOCKOUT_MAX);
+      let login_lockout_max: usize = loader.check(login_lockout_max_key, validate_minimum(login_lockout_max, login_lockout_base)).unwrap_or(DEFAULT_LOGIN_LOCKOUT_MAX);
+
+      let login_failure_window_key: ConfigKey = key("LOGIN_FAILURE_WINDOW", "login.failure_window");
+      let login_failure_window: usize = loader.read(login_failure_window_key, file_config.login.failure_window, parse_usize).unwrap_or(DEFAULT_LOGIN_FAILURE_WINDOW);
+      let login_failure_window: usize = loader.check(login_failure_window_key, validate_positive(login_failure_window)).unwrap_or(DEFAULT_LOGIN_FAILURE_WINDOW);
+
+      // Election
+      let secret_ballot_key: ConfigKey = key("SECRET_BALLOT", "election.secret_ballot");
+      let secret_ballot: bool = loader.read(secret_ballot_key, file_config.election.secret_ballot, parse_bool).unwrap_or(false);
+
+      let results_embargo_key: ConfigKey = key("RESULTS_EMBARGO", "election.results_embargo");
+      let results_embargo: bool = loader.read(results_embargo_key, file_config.election.results_embargo, parse_bool).unwrap_or(false);
+      let results_embargo: bool = loader.check(results_embargo_key, validate_results_embargo(results_embargo, secret_ballot)).unwrap_or(false);
+
+      let receipt_secret_key: ConfigKey = key("RECEIPT_SECRET", "election.receipt_secret");
+      let receipt_secret: Option<String> = loader.read(receipt_secret_key, file_config.election.receipt_secret, parse_string);
+      let receipt_secret: Option<String> = receipt_secret.and_then(|data| loader.check(receipt_secret_key, validate_receipt_secret(data)));
+
+      // Timezone
+      let timezone_key: ConfigKey = key("TIMEZONE", "timezone");
+      let timezone: UtcOffset = loader.read_parsed(timezone_key, file_config.timezone, parse_timezone).unwrap_or(DEFAULT_TIMEZONE);
+
+      // Every value is set when nothing was reported
+      match (bind_address, cors_allowed_origins, database_url, database_username, database_password, database_namespace, database_name, redis_url, redis_pool_size, admin_session_length, voter_length, voter_alphabet) {
+            (Some(bind_address), Some(cors_allowed_origins), Some(database_url), Some(database_username), Some(database_password), Some(database_namespace), Some(database_name), Some(redis_url), Some(redis_pool_size), Some(admin_session_length), Some(voter_length), Some(voter_alphabet)) if loader.errors.is_empty() => {
+                  Ok(Config {
+                        server: ServerConfig { bind_address, workers, cors_allowed_origins, trusted_proxies },
+                        database: DatabaseConfig { url: database_url, username: database_username, password: database_password, namespace: database_namespace, name: database_name },
+                        redis: RedisConfig { url: redis_url, pool_size: redis_pool_size, leader_lock_timeout: leader_lock_timeout as i64 },
+                        cookie: CookieConfig { secure: cookie_secure, same_site: cookie_same_site, domain: cookie_domain },
+                        token: TokenConfig { admin_session_length, voter_length, voter_alphabet, voter_checksum },
+                        session: SessionConfig { idle_timeout: session_idle_timeout as i64, absolute_timeout: session_absolute_timeout as i64 },
+                        login: LoginLimitConfig {
+                              attempt_limit: login_attempt_limit as i64,
+                              lockout_base: login_lockout_base as i64,
+                              lockout_max: login_lockout_max as i64,
+                              failure_window: login_failure_window as i64
+                        },
+                        election: ElectionConfig { secret_ballot, results_embargo, receipt_secret },
+                        timezone
+                  })
+            },
+            _ => Err(loader.errors)
+      }
+}
+
+/// Load the config from the environment and the optional `config.toml`, or the file set in `CONFIG_FILE`.
+pub fn init_config() -> Result<(), Vec<ConfigError>> {
+      let config: Config = load_config()?;
+      let _ = CONFIG.set(config);
+
+      Ok(())
+}
+
+/// Panics when it's called before `init_config`.
+pub fn get_config() -> &'static Config {
+      CONFIG.get().expect("The config is loaded on startup.")
+}
+
+/// The timezone of the logs, which are also written while the config is loading.
+pub fn get_timezone() -> UtcOffset {
+      CONFIG.get().map(|config| config.timezone).unwrap_or(DEFAULT_TIMEZONE)
+}
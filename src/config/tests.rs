@@ -0,0 +1,74 @@
+use super::*;
+
+// Never set, so only the config file value is read
+const UNSET_SECRET_KEY: ConfigKey = key("KPRS_TEST_UNSET_SECRET", "test.unset_secret");
+
+#[test]
+fn timezone_accepts_utc_and_offsets() {
+      assert_eq!(parse_timezone("UTC"), Ok(UtcOffset::UTC));
+      assert_eq!(parse_timezone("Z"), Ok(UtcOffset::UTC));
+      assert_eq!(parse_timezone("+7"), Ok(offset!(+7)));
+      assert_eq!(parse_timezone("+07:00"), Ok(offset!(+7)));
+      assert_eq!(parse_timezone("-03:30"), Ok(offset!(-3:30)));
+}
+
+#[test]
+fn timezone_rejects_out_of_range_and_malformed_offsets() {
+      assert!(parse_timezone("+15").is_err());
+      assert!(parse_timezone("+07:60").is_err());
+      assert!(parse_timezone("7").is_err());
+      assert!(parse_timezone("+seven").is_err());
+      assert!(parse_timezone("").is_err());
+}
+
+#[test]
+fn origins_accept_a_lone_wildcard_and_exact_origins() {
+      assert!(validate_origins(vec![String::from("*")]).is_ok());
+      assert!(validate_origins(vec![String::from("https://kprs.example.com"), String::from("http://localhost:3000")]).is_ok());
+      assert!(validate_origins(Vec::new()).is_ok());
+}
+
+#[test]
+fn origins_reject_a_combined_wildcard_and_malformed_origins() {
+      assert!(validate_origins(vec![String::from("*"), String::from("https://kprs.example.com")]).is_err());
+      assert!(validate_origins(vec![String::from("kprs.example.com")]).is_err());
+      assert!(validate_origins(vec![String::from("https://kprs.example.com/")]).is_err());
+}
+
+#[test]
+fn positive_numbers_reject_zero() {
+      assert_eq!(validate_positive(1), Ok(1));
+      assert!(validate_positive(0).is_err());
+      assert!(parse_usize("-1").is_err());
+}
+
+#[test]
+fn missing_secret_is_reported() {
+      let mut loader: ConfigLoader = ConfigLoader::default();
+      let secret: Option<String> = loader.read(UNSET_SECRET_KEY, None, parse_string);
+
+      assert_eq!(loader.require(UNSET_SECRET_KEY, secret), None);
+      assert!(matches!(loader.errors.as_slice(), [ConfigError::Missing(ConfigKey { env: "KPRS_TEST_UNSET_SECRET", .. })]));
+}
+
+#[test]
+fn secret_from_the_config_file_is_kept() {
+      let mut loader: ConfigLoader = ConfigLoader::default();
+      let secret: Option<String> = loader.read(UNSET_SECRET_KEY, Some(String::from("pass")), parse_string);
+
+      assert_eq!(loader.require(UNSET_SECRET_KEY, secret), Some(String::from("pass")));
+      assert!(loader.errors.is_empty());
+}
+
+#[test]
+fn short_receipt_secret_is_rejected() {
+      assert!(validate_receipt_secret(String::from("too short")).is_err());
+      assert!(validate_receipt_secret("a".repeat(MIN_RECEIPT_SECRET_LENGTH)).is_ok());
+}
+
+#[test]
+fn results_embargo_is_required_in_secret_ballot_mode() {
+      assert!(validate_results_embargo(false, true).is_err());
+      assert_eq!(validate_results_embargo(true, true), Ok(true));
+      assert_eq!(validate_results_embargo(false, false), Ok(false));
+}
@@ -1,9 +1,10 @@
 use std::{collections::HashMap, sync::Arc};
 
 use once_cell::sync::Lazy;
+use surrealdb::Action;
 use tokio::sync::RwLock;
 
-use crate::{db::{Admin, get_all_admins}, util::{log_error, log_something}};
+use crate::{data::cache_status::{set_cache_load_failed, set_cache_loaded}, db::{Admin, get_all_admins}, util::{log_error, log_something}};
 
 
 pub static ADMIN_DATA: Lazy<Arc<RwLock<HashMap<String, Admin>>>> = Lazy::new(|| {
@@ -16,7 +17,8 @@ pub async fn update_admin_data() {
       let admin_data: Vec<Admin> = match admin_data {
             Ok(data) => data,
             Err(err) => {
-                  log_error("StaticData", format!("There's an error when trying to get static data from database. Error: {}", err.to_string()).as_str());
+                  log_error("StaticData", format!("There's an error when trying to get static data from database. Error: {}", err).as_str());
+                  set_cache_load_failed("admins").await;
                   return;
             }
       };
@@ -30,11 +32,35 @@ pub async fn update_admin_data() {
       }
 
 
+      set_cache_loaded("admins").await;
+
       log_something("StaticData", "Static users data successfully updated!");
 }
 
+/// Apply a single notification of the live query instead of reading the whole table again.
+pub async fn apply_admin_change(action: Action, admin: Admin) {
+      let mut locked_write_admin_data = ADMIN_DATA.write().await;
+
+      match action {
+            Action::Create => {
+                  locked_write_admin_data.insert(admin.admin_id.clone(), admin);
+            },
+            // The previous admin ID is unknown when it's changed, read everything again
+            Action::Update if locked_write_admin_data.contains_key(&admin.admin_id) => {
+                  locked_write_admin_data.insert(admin.admin_id.clone(), admin);
+            },
+            Action::Delete => {
+                  locked_write_admin_data.remove(&admin.admin_id);
+            },
+            _ => {
+                  drop(locked_write_admin_data);
+                  update_admin_data().await;
+            }
+      }
+}
+
 pub fn get_all_admin_data() -> Arc<RwLock<HashMap<String, Admin>>> {
-      return ADMIN_DATA.clone()
+      ADMIN_DATA.clone()
 }
 
 pub async fn init_admin_data() {
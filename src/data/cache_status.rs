@@ -0,0 +1,46 @@
+use std::{collections::HashMap, sync::Arc};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::util::get_timestamp;
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct CacheStatusType {
+      // When the cache was last read in full from the database
+      pub loaded_at: Option<i64>,
+      // When the last load failed, the error is only logged
+      pub failed_at: Option<i64>
+}
+
+/// The status of each static data cache by name.
+static CACHE_STATUS: Lazy<Arc<RwLock<HashMap<&'static str, CacheStatusType>>>> = Lazy::new(|| {
+      Arc::new(RwLock::new(HashMap::new()))
+});
+
+pub async fn set_cache_loaded(cache: &'static str) {
+      let mut locked_write_cache_status = CACHE_STATUS.write().await;
+      let status: &mut CacheStatusType = locked_write_cache_status.entry(cache).or_default();
+
+      status.loaded_at = Some(get_timestamp());
+}
+
+/// The cache keeps the data of its last load, if any.
+pub async fn set_cache_load_failed(cache: &'static str) {
+      let mut locked_write_cache_status = CACHE_STATUS.write().await;
+      let status: &mut CacheStatusType = locked_write_cache_status.entry(cache).or_default();
+
+      status.failed_at = Some(get_timestamp());
+}
+
+pub async fn get_cache_status() -> HashMap<&'static str, CacheStatusType> {
+      CACHE_STATUS.read().await.clone()
+}
+
+/// Every cache was loaded at least once.
+pub async fn is_caches_loaded() -> bool {
+      let locked_cache_status = CACHE_STATUS.read().await;
+
+      !locked_cache_status.is_empty() && locked_cache_status.values().all(|status| status.loaded_at.is_some())
+}
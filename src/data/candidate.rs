@@ -1,32 +1,52 @@
-use tokio::sync::OnceCell;
+use std::sync::Arc;
 
-use crate::{db::{Candidate, get_all_candidates}, util::{log_error, log_something}};
+use once_cell::sync::Lazy;
+use tokio::sync::RwLock;
 
+use crate::{data::cache_status::{set_cache_load_failed, set_cache_loaded}, db::{Candidate, get_all_candidates}, util::{log_error, log_something}};
 
-pub static CANDIDATES_DATA: OnceCell<Vec<Candidate>> = OnceCell::const_new();
 
-pub async fn get_candidates_data<'a>() -> &'a Vec<Candidate> {
-      let data = CANDIDATES_DATA.get_or_init(async || {
-            // Get the candidate data
-            let db_all_candidates = get_all_candidates().await;
-            let db_all_candidates: Vec<Candidate> = match db_all_candidates {
-                  Ok(data) => data,
-                  Err(err) => {
-                        log_error("StaticData", format!("There's an error when trying to get static data from database. Error: {}", err.to_string()).as_str());
-                        return Vec::new();
-                  }
-            };
+/// Every candidate including the withdrawn ones, sorted by their ballot position.
+static CANDIDATES_DATA: Lazy<Arc<RwLock<Vec<Candidate>>>> = Lazy::new(|| {
+      Arc::new(RwLock::new(Vec::new()))
+});
 
-            // Log the success message
-            log_something("StaticData", "Static candidates data successfully initialized.");
+pub async fn update_candidates_data() {
+      // Get the candidate data
+      let db_all_candidates = get_all_candidates().await;
+      let mut db_all_candidates: Vec<Candidate> = match db_all_candidates {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("StaticData", format!("There's an error when trying to get all candidates from database. Error: {}", err).as_str());
+                  set_cache_load_failed("candidates").await;
+                  return;
+            }
+      };
 
-            // Return the result
-            db_all_candidates
-      }).await;
+      db_all_candidates.sort_by(|a, b| a.position.cmp(&b.position).then_with(|| a.president.cmp(&b.president)));
 
-      return &data;
+      // Replace the static candidates data
+      *CANDIDATES_DATA.write().await = db_all_candidates;
+
+      set_cache_loaded("candidates").await;
+
+      // Log the success message
+      log_something("StaticData", "Static candidates data successfully updated!");
+}
+
+pub fn get_all_candidates_data() -> Arc<RwLock<Vec<Candidate>>> {
+      CANDIDATES_DATA.clone()
+}
+
+/// Candidates that can still be voted for.
+pub async fn get_candidates_data() -> Vec<Candidate> {
+      CANDIDATES_DATA.read().await
+            .iter()
+            .filter(|candidate| !candidate.withdrawn)
+            .cloned()
+            .collect()
 }
 
 pub async fn init_candidates_data() {
-      get_candidates_data().await;
+      update_candidates_data().await;
 }
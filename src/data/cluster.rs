@@ -0,0 +1,39 @@
+use std::{sync::atomic::{AtomicBool, Ordering}, time::Duration};
+
+use deadpool_redis::Pool as RedisPool;
+use once_cell::sync::Lazy;
+use surrealdb::Uuid;
+
+use crate::{config::get_config, rdb::renew_leader_lock_redis, util::log_something};
+
+/// Identifies this replica in the leader lock.
+pub static INSTANCE_ID: Lazy<String> = Lazy::new(|| {
+      Uuid::new_v4().to_string()
+});
+
+/// Every replica runs the same live queries, only the leader publishes the resulting
+/// live messages and runs the election scheduler so they aren't repeated per replica.
+static IS_LEADER: AtomicBool = AtomicBool::new(false);
+
+pub fn is_leader() -> bool {
+      IS_LEADER.load(Ordering::Relaxed)
+}
+
+/// Keep renewing the leader lock, another replica takes over when this one stops.
+pub fn spawn_leader_election(redis_pool: RedisPool) {
+      tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs((get_config().redis.leader_lock_timeout / 3) as u64));
+
+            loop {
+                  interval.tick().await;
+
+                  let is_now_leader: bool = renew_leader_lock_redis(&redis_pool, INSTANCE_ID.as_str()).await;
+                  let was_leader: bool = IS_LEADER.swap(is_now_leader, Ordering::Relaxed);
+
+                  if is_now_leader != was_leader {
+                        let message: &str = if is_now_leader { "This instance is now the leader." } else { "This instance is no longer the leader." };
+                        log_something("Cluster", message);
+                  }
+            }
+      });
+}
@@ -0,0 +1,135 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use once_cell::sync::Lazy;
+use strum::IntoEnumIterator;
+use tokio::sync::RwLock;
+
+use crate::{config::get_config, data::{cache_status::{set_cache_load_failed, set_cache_loaded}, cluster::is_leader, vote::get_votes_tally}, db::{Campus, Election, ElectionState, get_all_elections, upsert_election}, util::{get_timestamp, log_error, log_something}};
+
+
+pub static ELECTIONS_DATA: Lazy<Arc<RwLock<HashMap<Campus, Election>>>> = Lazy::new(|| {
+      let mut hashmap_result: HashMap<Campus, Election> = HashMap::new();
+
+      for campus in Campus::iter() {
+            hashmap_result.insert(campus, Election::new(campus));
+      }
+
+      Arc::new(RwLock::new(hashmap_result))
+});
+
+pub async fn update_elections_data() {
+      // Get the election data from database
+      let db_all_elections = get_all_elections().await;
+      let db_all_elections: Vec<Election> = match db_all_elections {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("StaticData", format!("There's an error when trying to get all elections from database. Error: {}", err).as_str());
+                  set_cache_load_failed("elections").await;
+                  return;
+            }
+      };
+
+      // Campuses without an election row stays in draft
+      let mut locked_write_elections_data = ELECTIONS_DATA.write().await;
+      for campus in Campus::iter() {
+            locked_write_elections_data.insert(campus, Election::new(campus));
+      }
+      for election in db_all_elections {
+            locked_write_elections_data.insert(election.campus, election);
+      }
+
+      set_cache_loaded("elections").await;
+
+      log_something("StaticData", "Static elections data successfully updated!");
+}
+
+pub fn get_elections_data() -> Arc<RwLock<HashMap<Campus, Election>>> {
+      ELECTIONS_DATA.clone()
+}
+
+/// `e:<campus>,<state>,<starts_at>,<ends_at>,<server time>`, unscheduled times are left empty.
+pub fn get_election_message(election: &Election) -> String {
+      let current_timestamp: i64 = get_timestamp();
+      let format_timestamp = |timestamp: Option<i64>| timestamp.map(|data| data.to_string()).unwrap_or_default();
+
+      format!(
+            "e:{},{},{},{},{}",
+            election.campus.as_str(),
+            election.get_state_at(current_timestamp).as_str(),
+            format_timestamp(election.starts_at),
+            format_timestamp(election.ends_at),
+            current_timestamp
+      )
+}
+
+pub async fn get_election_state(campus: &Campus) -> ElectionState {
+      let locked_elections_data = ELECTIONS_DATA.read().await;
+
+      match locked_elections_data.get(campus) {
+            Some(election) => election.get_state_at(get_timestamp()),
+            None => ElectionState::Draft,
+      }
+}
+
+pub async fn is_results_embargoed(campus: &Campus) -> bool {
+      get_config().election.results_embargo && get_election_state(campus).await != ElectionState::Closed
+}
+
+/// `r:<campus>,<candidate_id>=<votes>,...` sent when the embargo of a campus is lifted.
+pub async fn get_results_message(campus: &Campus) -> String {
+      let mut message: String = format!("r:{}", campus.as_str());
+
+      for (candidate_id, votes) in get_votes_tally(campus).await {
+            message += format!(",{}={}", candidate_id, votes).as_str();
+      }
+
+      message
+}
+
+/// Persist the scheduled open and close so the live query broadcast them.
+/// Only the leader persists them, the other replicas follow through their live query.
+pub fn spawn_election_scheduler() {
+      tokio::spawn(async {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+
+            loop {
+                  interval.tick().await;
+
+                  if !is_leader() {
+                        continue;
+                  }
+
+                  let current_timestamp: i64 = get_timestamp();
+                  let scheduled_elections: Vec<Election> = ELECTIONS_DATA.read().await
+                        .values()
+                        .filter(|election| election.get_state_at(current_timestamp) != election.state)
+                        .map(|election| Election {
+                              state: election.get_state_at(current_timestamp),
+                              ..election.clone()
+                        })
+                        .collect();
+
+                  for election in scheduled_elections {
+                        let campus: Campus = election.campus;
+                        let state: ElectionState = election.state;
+
+                        // Update the static data right away so the next tick doesn't repeat it
+                        ELECTIONS_DATA.write().await.insert(campus, election.clone());
+
+                        match upsert_election(election).await {
+                              Ok(_) => {
+                                    log_something("ElectionScheduler", format!("The election of {} is now {}.", campus.as_str(), state.as_str()).as_str());
+                              },
+                              Err(err) => {
+                                    log_error("ElectionScheduler", format!("There's an error when trying to update the election state. Error: {}", err).as_str());
+                              }
+                        }
+                  }
+            }
+      });
+}
+
+pub async fn init_elections_data() {
+      update_elections_data().await;
+      spawn_election_scheduler();
+}
@@ -1,12 +1,106 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use deadpool_redis::Pool as RedisPool;
+use futures_util::StreamExt;
 use once_cell::sync::Lazy;
-use tokio::sync::RwLock;
+use surrealdb::Uuid;
+use tokio::sync::{OnceCell, RwLock};
 
-pub static LIVE_CLIENTS: Lazy<Arc<RwLock<HashMap<String, actix_ws::Session>>>> = Lazy::new(|| {
+use crate::{data::cluster::is_leader, rdb::{LiveChannel, publish_live_message_redis, subscribe_live_messages_redis}, util::{log_error, log_something}};
+
+pub type LiveClientsType = Arc<RwLock<HashMap<String, actix_ws::Session>>>;
+
+/// Public clients, only receives aggregated counts.
+pub static LIVE_CLIENTS: Lazy<LiveClientsType> = Lazy::new(|| {
+      Arc::new(RwLock::new(HashMap::new()))
+});
+
+/// Superadmin clients, receives who voted for whom.
+pub static ADMIN_LIVE_CLIENTS: Lazy<LiveClientsType> = Lazy::new(|| {
       Arc::new(RwLock::new(HashMap::new()))
 });
 
-pub fn get_live_clients() -> Arc<RwLock<HashMap<String, actix_ws::Session>>> {
+/// Used to publish the live messages to every replica.
+static LIVE_PUBLISHER: OnceCell<RedisPool> = OnceCell::const_new();
+
+pub fn get_live_clients() -> LiveClientsType {
       LIVE_CLIENTS.clone()
 }
+
+pub fn get_admin_live_clients() -> LiveClientsType {
+      ADMIN_LIVE_CLIENTS.clone()
+}
+
+async fn broadcast_message(live_clients: &LiveClientsType, message: &str) {
+      let mut locked_write_live_clients = live_clients.write().await;
+
+      for (_, session) in locked_write_live_clients.iter_mut() {
+            let _ = session.text(message).await;
+      }
+}
+
+async fn publish_message(channel: LiveChannel, message: &str) {
+      // Only the leader publishes so the replicas don't send the same change multiple times
+      if !is_leader() {
+            return;
+      }
+
+      let Some(redis_pool) = LIVE_PUBLISHER.get() else {
+            return;
+      };
+
+      let _ = publish_live_message_redis(redis_pool, channel, message).await;
+}
+
+/// Send to the public clients of every replica.
+pub async fn broadcast_live_message(message: &str) {
+      publish_message(LiveChannel::Public, message).await;
+}
+
+/// Send to the superadmin clients of every replica.
+pub async fn broadcast_admin_live_message(message: &str) {
+      publish_message(LiveChannel::Admin, message).await;
+}
+
+async fn forward_live_message(channel: LiveChannel, message: String) {
+      match channel {
+            LiveChannel::Admin => broadcast_message(&ADMIN_LIVE_CLIENTS, message.as_str()).await,
+            LiveChannel::Public => broadcast_message(&LIVE_CLIENTS, message.as_str()).await,
+      }
+}
+
+/// Forward the published live messages to the clients connected to this replica.
+pub async fn init_live_broadcast(redis_pool: RedisPool, redis_client: redis::Client) {
+      let _ = LIVE_PUBLISHER.set(redis_pool);
+
+      tokio::spawn(async move {
+            loop {
+                  if let Err(err) = subscribe_live_messages_redis(&redis_client, forward_live_message).await {
+                        log_error("LiveSubscriber", format!("There's an error when subscribing to the live messages. Error: {}", err).as_str());
+                  }
+
+                  log_something("LiveSubscriber", "Resubscribing to the live messages...");
+                  tokio::time::sleep(Duration::from_secs(1)).await;
+            }
+      });
+}
+
+/// Register the session and keep answering pings until the client disconnects.
+pub async fn serve_live_client(live_clients: LiveClientsType, mut session: actix_ws::Session, mut msg_stream: actix_ws::MessageStream) {
+      let client_id: String = Uuid::new_v4().to_string();
+      {
+            let mut locked_write_live_clients = live_clients.write().await;
+            locked_write_live_clients.insert(client_id.clone(), session.clone());
+      }
+
+      actix_web::rt::spawn(async move {
+            while let Some(Ok(msg)) = msg_stream.next().await {
+                  if let actix_ws::Message::Ping(bytes) = msg {
+                        let _ = session.pong(&bytes).await;
+                  }
+            }
+
+            let mut locked_write_live_clients = live_clients.write().await;
+            locked_write_live_clients.remove(&client_id);
+      });
+}
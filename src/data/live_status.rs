@@ -0,0 +1,58 @@
+use std::{collections::HashMap, sync::Arc};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use crate::util::get_timestamp;
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct LiveQueryStatusType {
+      pub connected: bool,
+      pub restarts: u32,
+      // When the live query was last connected or disconnected
+      pub since: i64
+}
+
+/// The status of each SurrealDB live query by table name.
+static LIVE_QUERY_STATUS: Lazy<Arc<RwLock<HashMap<&'static str, LiveQueryStatusType>>>> = Lazy::new(|| {
+      Arc::new(RwLock::new(HashMap::new()))
+});
+
+pub async fn register_live_query(table: &'static str) {
+      LIVE_QUERY_STATUS.write().await.entry(table).or_default();
+}
+
+/// Returns true when the live query was connected before, so the caches may have missed changes.
+pub async fn set_live_query_connected(table: &'static str) -> bool {
+      let mut locked_write_live_query_status = LIVE_QUERY_STATUS.write().await;
+      let status: &mut LiveQueryStatusType = locked_write_live_query_status.entry(table).or_default();
+
+      status.connected = true;
+      status.since = get_timestamp();
+
+      status.restarts > 0
+}
+
+/// Returns true when the live query was connected, so the backoff can start over. The error is only logged.
+pub async fn set_live_query_disconnected(table: &'static str) -> bool {
+      let mut locked_write_live_query_status = LIVE_QUERY_STATUS.write().await;
+      let status: &mut LiveQueryStatusType = locked_write_live_query_status.entry(table).or_default();
+
+      let was_connected: bool = status.connected;
+      status.connected = false;
+      status.restarts += 1;
+      status.since = get_timestamp();
+
+      was_connected
+}
+
+pub async fn get_live_query_status() -> HashMap<&'static str, LiveQueryStatusType> {
+      LIVE_QUERY_STATUS.read().await.clone()
+}
+
+pub async fn is_live_queries_connected() -> bool {
+      let locked_live_query_status = LIVE_QUERY_STATUS.read().await;
+
+      !locked_live_query_status.is_empty() && locked_live_query_status.values().all(|status| status.connected)
+}
@@ -3,3 +3,7 @@ pub mod vote;
 pub mod candidate;
 pub mod admin;
 pub mod live_clients;
+pub mod election;
+pub mod cluster;
+pub mod live_status;
+pub mod cache_status;
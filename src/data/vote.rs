@@ -2,10 +2,24 @@ use std::{collections::HashMap, sync::Arc};
 
 use once_cell::sync::Lazy;
 use strum::IntoEnumIterator;
+use surrealdb::Action;
 use tokio::sync::RwLock;
-use crate::{db::{Campus, Vote, get_all_votes}, util::{log_error, log_something}};
+use crate::{config::get_config, data::{cache_status::{set_cache_load_failed, set_cache_loaded}, candidate::get_candidates_data, voter::get_voters_data}, db::{Ballot, Campus, Participation, Vote, get_all_ballots, get_all_participations, get_all_votes}, error::AppResult, util::{log_error, log_something}};
 
-pub static VOTES_COUNT: Lazy<Arc<HashMap<Campus, RwLock<HashMap<String, String>>>>> = Lazy::new(|| {
+#[cfg(test)]
+mod tests;
+
+pub type VotesCountType = HashMap<Campus, RwLock<HashMap<String, String>>>;
+pub type BallotsTallyType = HashMap<Campus, RwLock<HashMap<String, usize>>>;
+
+/// Votes are stored as a participation and an anonymous ballot in secret ballot mode,
+/// so `VOTES_COUNT` only knows who has voted and maps them to an empty candidate ID.
+pub fn is_secret_ballot() -> bool {
+      get_config().election.secret_ballot
+}
+
+/// The candidate ID chosen by each voter ID of every campus.
+pub static VOTES_COUNT: Lazy<Arc<VotesCountType>> = Lazy::new(|| {
       let mut hashmap_result: HashMap<Campus, RwLock<HashMap<String, String>>> = HashMap::new();
 
       for campus in Campus::iter() {
@@ -15,18 +29,229 @@ pub static VOTES_COUNT: Lazy<Arc<HashMap<Campus, RwLock<HashMap<String, String>>
       Arc::new(hashmap_result)
 });
 
+/// Votes of each candidate in secret ballot mode.
+pub static BALLOTS_TALLY: Lazy<Arc<BallotsTallyType>> = Lazy::new(|| {
+      let mut hashmap_result: BallotsTallyType = HashMap::new();
+
+      for campus in Campus::iter() {
+            hashmap_result.insert(campus, RwLock::new(HashMap::new()));
+      }
+
+      Arc::new(hashmap_result)
+});
+
+/// Running counts of the votes in a campus, kept along `VOTES_COUNT` so reading them doesn't go through every vote.
+#[derive(Default)]
+struct VotesTallyType {
+      // Votes of each candidate, counted by `BALLOTS_TALLY` instead in secret ballot mode
+      candidates: HashMap<String, usize>,
+      // Votes of each voter class
+      classes: HashMap<String, usize>,
+      // The class each vote was counted in, since the class of a voter can change after voting
+      voter_classes: HashMap<String, String>
+}
+
+impl VotesTallyType {
+      fn add(&mut self, voter_id: &str, candidate_id: &str, voter_class: Option<String>) {
+            *self.candidates.entry(candidate_id.to_string()).or_insert(0) += 1;
+
+            if let Some(voter_class) = voter_class {
+                  *self.classes.entry(voter_class.clone()).or_insert(0) += 1;
+                  self.voter_classes.insert(voter_id.to_string(), voter_class);
+            }
+      }
+
+      fn remove(&mut self, voter_id: &str, candidate_id: &str) {
+            decrement_counter(&mut self.candidates, candidate_id);
+
+            if let Some(voter_class) = self.voter_classes.remove(voter_id) {
+                  decrement_counter(&mut self.classes, voter_class.as_str());
+            }
+      }
+}
+
+/// Counters that reach zero are removed, the same as a candidate or class without any vote.
+fn decrement_counter(counters: &mut HashMap<String, usize>, key: &str) {
+      if let Some(counter) = counters.get_mut(key) {
+            *counter = counter.saturating_sub(1);
+            if *counter == 0 {
+                  counters.remove(key);
+            }
+      }
+}
+
+/// Always locked after the campus of `VOTES_COUNT` it counts.
+static VOTES_TALLY: Lazy<Arc<HashMap<Campus, RwLock<VotesTallyType>>>> = Lazy::new(|| {
+      let mut hashmap_result: HashMap<Campus, RwLock<VotesTallyType>> = HashMap::new();
+
+      for campus in Campus::iter() {
+            hashmap_result.insert(campus, RwLock::new(VotesTallyType::default()));
+      }
+
+      Arc::new(hashmap_result)
+});
+
+async fn get_all_votes_per_campus(campus: Campus) -> AppResult<Vec<Vote>> {
+      if !is_secret_ballot() {
+            return get_all_votes(Some(campus)).await;
+      }
+
+      let participations: Vec<Participation> = get_all_participations(Some(campus)).await?;
+      Ok(participations
+            .into_iter()
+            .map(participation_to_vote)
+            .collect())
+}
+
+/// The candidate of a participation is unknown.
+pub fn participation_to_vote(participation: Participation) -> Vote {
+      Vote {
+            voter_id: participation.voter_id,
+            voter_name: participation.voter_name,
+            candidate_id: String::new(),
+            campus: participation.campus,
+            ballot_id: String::new()
+      }
+}
+
+pub async fn update_ballots_tally() {
+      for campus in Campus::iter() {
+            let result = get_all_ballots(Some(campus)).await;
+            let result: Vec<Ballot> = match result {
+                  Ok(data) => data,
+                  Err(err) => {
+                        log_error("StaticData", format!("There's an error when trying to get all ballots from database. Error: {}", err).as_str());
+                        set_cache_load_failed("ballots").await;
+                        return;
+                  }
+            };
+
+            let mut ballots_tally: HashMap<String, usize> = HashMap::new();
+            for ballot in result {
+                  ballots_tally
+                        .entry(ballot.candidate_id)
+                        .and_modify(|counter| {
+                              *counter += 1;
+                        })
+                        .or_insert(1);
+            }
+
+            if let Some(static_ballots_tally) = BALLOTS_TALLY.get(&campus) {
+                  *static_ballots_tally.write().await = ballots_tally;
+            }
+      }
+
+      set_cache_loaded("ballots").await;
+}
+
+/// Apply a single notification of the ballot live query instead of counting every ballot again.
+pub async fn apply_ballot_change(action: Action, ballot: Ballot) {
+      let Some(static_ballots_tally) = BALLOTS_TALLY.get(&ballot.campus) else {
+            return;
+      };
+
+      match action {
+            Action::Create => {
+                  *static_ballots_tally.write().await.entry(ballot.candidate_id).or_insert(0) += 1;
+            },
+            Action::Delete => {
+                  let mut locked_write_ballots_tally = static_ballots_tally.write().await;
+                  if let Some(counter) = locked_write_ballots_tally.get_mut(&ballot.candidate_id) {
+                        *counter = counter.saturating_sub(1);
+                  }
+            },
+            // The previous candidate is unknown, count everything again
+            _ => {
+                  update_ballots_tally().await;
+            }
+      }
+}
+
+/// Apply a single notification of the vote or participation live query instead of reading every vote again.
+pub async fn apply_vote_change(action: Action, vote: Vote) {
+      match action {
+            Action::Create | Action::Update => {
+                  // Read before locking the votes, the voters are never locked after them
+                  let voter_class: Option<String> = get_voter_class(vote.voter_id.as_str()).await;
+
+                  // The campus may have been changed by the update
+                  for (campus, static_votes_data) in VOTES_COUNT.iter() {
+                        let Some(static_votes_tally) = VOTES_TALLY.get(campus) else {
+                              continue;
+                        };
+                        let mut locked_write_static_votes_data = static_votes_data.write().await;
+                        let mut locked_write_votes_tally = static_votes_tally.write().await;
+
+                        if *campus == vote.campus {
+                              insert_vote_data(&mut locked_write_static_votes_data, &mut locked_write_votes_tally, &vote, voter_class.clone());
+                        } else {
+                              remove_vote_data(&mut locked_write_static_votes_data, &mut locked_write_votes_tally, vote.voter_id.as_str());
+                        }
+                  }
+            },
+            Action::Delete => {
+                  forget_vote(&vote.campus, vote.voter_id.as_str()).await;
+            },
+            _ => {
+                  update_votes_data().await;
+            }
+      }
+}
+
+/// Record a vote once the database confirmed it, the live query notification of the same vote changes nothing after it.
+pub async fn record_vote(vote: Vote) {
+      apply_vote_change(Action::Create, vote).await;
+}
+
+/// Forget the vote of a voter removed from the database, either before or after its live query notification.
+pub async fn forget_vote(campus: &Campus, voter_id: &str) {
+      if let (Some(static_votes_data), Some(static_votes_tally)) = (VOTES_COUNT.get(campus), VOTES_TALLY.get(campus)) {
+            let mut locked_write_static_votes_data = static_votes_data.write().await;
+            remove_vote_data(&mut locked_write_static_votes_data, &mut *static_votes_tally.write().await, voter_id);
+      }
+}
+
+/// Every write of `VOTES_COUNT` goes through here so `VOTES_TALLY` always counts the same votes.
+fn insert_vote_data(votes_data: &mut HashMap<String, String>, votes_tally: &mut VotesTallyType, vote: &Vote, voter_class: Option<String>) {
+      let previous_candidate_id: Option<String> = votes_data.insert(vote.voter_id.clone(), vote.candidate_id.clone());
+      match previous_candidate_id {
+            Some(previous_candidate_id) if previous_candidate_id == vote.candidate_id => return,
+            Some(previous_candidate_id) => votes_tally.remove(vote.voter_id.as_str(), previous_candidate_id.as_str()),
+            None => (),
+      }
+
+      votes_tally.add(vote.voter_id.as_str(), vote.candidate_id.as_str(), voter_class);
+}
+
+fn remove_vote_data(votes_data: &mut HashMap<String, String>, votes_tally: &mut VotesTallyType, voter_id: &str) {
+      if let Some(previous_candidate_id) = votes_data.remove(voter_id) {
+            votes_tally.remove(voter_id, previous_candidate_id.as_str());
+      }
+}
+
+async fn get_voter_class(voter_id: &str) -> Option<String> {
+      get_voters_data().read().await
+            .get(voter_id)
+            .map(|voter| voter.class.clone())
+}
+
 pub async fn update_votes_data() {
+      if is_secret_ballot() {
+            update_ballots_tally().await;
+      }
+
       // Get the votes data
       let mut db_all_votes_data: HashMap<Campus, Vec<Vote>> = HashMap::new();
 
       // Iterate for each campus
       for campus in Campus::iter() {
             // Get all of the data of that particular campus
-            let result = get_all_votes(Some(campus)).await;
+            let result = get_all_votes_per_campus(campus).await;
             let result: Vec<Vote> = match result {
                   Ok(data) => data,
                   Err(err) => {
-                        log_error("StaticData", format!("There's an error when trying to get all votes from postgres. Error: {}", err.to_string()).as_str());
+                        log_error("StaticData", format!("There's an error when trying to get all votes from postgres. Error: {}", err).as_str());
+                        set_cache_load_failed("votes").await;
                         return;
                   }
             };
@@ -48,22 +273,97 @@ pub async fn update_votes_data() {
                   }
             };
 
+            let static_votes_tally = match VOTES_TALLY.get(&all_votes_data.0) {
+                  Some(data) => data,
+                  None => {
+                        log_error("StaticData", "There's an error where campus enum as key is not aggregated yet.");
+                        return;
+                  }
+            };
+
+            // Read before locking the votes, the voters are never locked after them
+            let voter_classes: HashMap<String, String> = {
+                  let voters_data = get_voters_data();
+                  let locked_voters_data = voters_data.read().await;
+                  all_votes_data.1
+                        .iter()
+                        .filter_map(|vote| locked_voters_data.get(&vote.voter_id).map(|voter| (vote.voter_id.clone(), voter.class.clone())))
+                        .collect()
+            };
+
             // Get the locked write state of static votes data
             let mut locked_write_static_votes_data = static_votes_data.write().await;
             locked_write_static_votes_data.clear();
+            let mut votes_tally: VotesTallyType = VotesTallyType::default();
 
             // Iterate for each voter data in current campus
             for votes_data_per_campus in all_votes_data.1.iter() {
-                  locked_write_static_votes_data.insert(votes_data_per_campus.voter_name.clone(), votes_data_per_campus.candidate_name.clone());
+                  insert_vote_data(&mut locked_write_static_votes_data, &mut votes_tally, votes_data_per_campus, voter_classes.get(&votes_data_per_campus.voter_id).cloned());
             }
+
+            *static_votes_tally.write().await = votes_tally;
       }
 
+      set_cache_loaded("votes").await;
+
       // Log the success message
       log_something("StaticData", "Static votes data successfully initialized.");
 }
 
 
-pub fn get_votes_count() -> Arc<HashMap<Campus, RwLock<HashMap<String, String>>>> {
+/// Count the votes of each candidate in a campus.
+pub async fn get_votes_tally(campus: &Campus) -> HashMap<String, usize> {
+      if is_secret_ballot() {
+            return match BALLOTS_TALLY.get(campus) {
+                  Some(static_ballots_tally) => static_ballots_tally.read().await.clone(),
+                  None => HashMap::new(),
+            };
+      }
+
+      match VOTES_TALLY.get(campus) {
+            Some(static_votes_tally) => static_votes_tally.read().await.candidates.clone(),
+            None => HashMap::new(),
+      }
+}
+
+pub async fn get_votes_turnout(campus: &Campus) -> usize {
+      match VOTES_COUNT.get(campus) {
+            Some(static_votes_data) => static_votes_data.read().await.len(),
+            None => 0,
+      }
+}
+
+/// Count the votes of each class in a campus.
+pub async fn get_class_turnouts(campus: &Campus) -> HashMap<String, usize> {
+      match VOTES_TALLY.get(campus) {
+            Some(static_votes_tally) => static_votes_tally.read().await.classes.clone(),
+            None => HashMap::new(),
+      }
+}
+
+/// `c:<campus>,<candidate_id>,<votes>` for each candidate of the campus.
+pub async fn get_tally_messages(campus: &Campus) -> Vec<String> {
+      let votes_tally: HashMap<String, usize> = get_votes_tally(campus).await;
+
+      get_candidates_data().await
+            .iter()
+            .filter(|candidate| candidate.campus == *campus)
+            .map(|candidate| format!("c:{},{},{}", campus.as_str(), candidate.candidate_id, votes_tally.get(&candidate.candidate_id).unwrap_or(&0)))
+            .collect()
+}
+
+/// `t:<campus>,<votes>` followed by `tc:<campus>,<class>,<votes>` for each class with votes.
+pub async fn get_turnout_messages(campus: &Campus) -> Vec<String> {
+      let mut result: Vec<String> = vec![format!("t:{},{}", campus.as_str(), get_votes_turnout(campus).await)];
+
+      for (class, votes) in get_class_turnouts(campus).await {
+            result.push(format!("tc:{},{},{}", campus.as_str(), class, votes));
+      }
+
+      result
+}
+
+pub fn get_votes_count() -> Arc<VotesCountType> {
       VOTES_COUNT.clone()
 }
 
@@ -0,0 +1,92 @@
+use super::*;
+
+fn vote(voter_id: &str, candidate_id: &str, campus: Campus) -> Vote {
+      Vote {
+            voter_id: voter_id.to_string(),
+            voter_name: voter_id.to_string(),
+            candidate_id: candidate_id.to_string(),
+            campus,
+            ballot_id: String::new()
+      }
+}
+
+// The tally statics are shared by every test, so each test uses its own voters and candidates
+async fn get_candidate_votes(campus: &Campus, candidate_id: &str) -> usize {
+      VOTES_TALLY.get(campus).unwrap().read().await.candidates.get(candidate_id).copied().unwrap_or(0)
+}
+
+async fn has_voted(campus: &Campus, voter_id: &str) -> bool {
+      VOTES_COUNT.get(campus).unwrap().read().await.contains_key(voter_id)
+}
+
+#[test]
+fn same_vote_inserted_twice_is_counted_once() {
+      let mut votes_data: HashMap<String, String> = HashMap::new();
+      let mut votes_tally: VotesTallyType = VotesTallyType::default();
+
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:1", "candidate:1", Campus::MM), Some(String::from("XII-A")));
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:1", "candidate:1", Campus::MM), Some(String::from("XII-A")));
+
+      assert_eq!(votes_tally.candidates.get("candidate:1"), Some(&1));
+      assert_eq!(votes_tally.classes.get("XII-A"), Some(&1));
+}
+
+#[test]
+fn changed_vote_moves_the_counts() {
+      let mut votes_data: HashMap<String, String> = HashMap::new();
+      let mut votes_tally: VotesTallyType = VotesTallyType::default();
+
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:1", "candidate:1", Campus::MM), Some(String::from("XII-A")));
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:1", "candidate:2", Campus::MM), Some(String::from("XII-A")));
+
+      assert_eq!(votes_tally.candidates.get("candidate:1"), None);
+      assert_eq!(votes_tally.candidates.get("candidate:2"), Some(&1));
+      assert_eq!(votes_tally.classes.get("XII-A"), Some(&1));
+}
+
+#[test]
+fn removed_vote_is_uncounted_once() {
+      let mut votes_data: HashMap<String, String> = HashMap::new();
+      let mut votes_tally: VotesTallyType = VotesTallyType::default();
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:1", "candidate:1", Campus::MM), Some(String::from("XII-A")));
+      insert_vote_data(&mut votes_data, &mut votes_tally, &vote("voter:2", "candidate:1", Campus::MM), Some(String::from("XII-A")));
+
+      remove_vote_data(&mut votes_data, &mut votes_tally, "voter:1");
+      remove_vote_data(&mut votes_data, &mut votes_tally, "voter:1");
+
+      assert_eq!(votes_tally.candidates.get("candidate:1"), Some(&1));
+      assert_eq!(votes_tally.classes.get("XII-A"), Some(&1));
+      assert!(!votes_tally.voter_classes.contains_key("voter:1"));
+}
+
+#[tokio::test]
+async fn recorded_vote_followed_by_its_notification_is_counted_once() {
+      record_vote(vote("recorded:1", "recorded-candidate", Campus::MM)).await;
+      apply_vote_change(Action::Create, vote("recorded:1", "recorded-candidate", Campus::MM)).await;
+      apply_vote_change(Action::Create, vote("recorded:2", "recorded-candidate", Campus::MM)).await;
+      record_vote(vote("recorded:2", "recorded-candidate", Campus::MM)).await;
+
+      assert_eq!(get_candidate_votes(&Campus::MM, "recorded-candidate").await, 2);
+}
+
+#[tokio::test]
+async fn forgotten_vote_followed_by_its_notification_is_uncounted_once() {
+      record_vote(vote("forgotten:1", "forgotten-candidate", Campus::PD)).await;
+      record_vote(vote("forgotten:2", "forgotten-candidate", Campus::PD)).await;
+
+      forget_vote(&Campus::PD, "forgotten:1").await;
+      apply_vote_change(Action::Delete, vote("forgotten:1", "forgotten-candidate", Campus::PD)).await;
+
+      assert_eq!(get_candidate_votes(&Campus::PD, "forgotten-candidate").await, 1);
+      assert!(!has_voted(&Campus::PD, "forgotten:1").await);
+}
+
+#[tokio::test]
+async fn vote_moved_to_another_campus_is_only_counted_there() {
+      record_vote(vote("moved:1", "moved-candidate", Campus::MM)).await;
+      apply_vote_change(Action::Update, vote("moved:1", "moved-candidate", Campus::PD)).await;
+
+      assert_eq!(get_candidate_votes(&Campus::MM, "moved-candidate").await, 0);
+      assert_eq!(get_candidate_votes(&Campus::PD, "moved-candidate").await, 1);
+      assert!(!has_voted(&Campus::MM, "moved:1").await);
+}
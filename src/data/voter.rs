@@ -2,40 +2,105 @@ use std::{collections::HashMap, sync::Arc};
 use once_cell::sync::Lazy;
 use tokio::sync::RwLock;
 
-use crate::{db::{Voter, get_all_users}, util::{log_error, log_something}};
+use surrealdb::Action;
 
+use crate::{data::cache_status::{set_cache_load_failed, set_cache_loaded}, db::{Voter, get_all_users}, util::{log_error, log_something}};
 
+
+/// Voters by their student number.
 static USERS_DATA: Lazy<Arc<RwLock<HashMap<String, Voter>>>> = Lazy::new(|| {
       Arc::new(RwLock::new(HashMap::new()))
 });
 
+/// Student numbers by the initial token, always locked after `USERS_DATA`.
+static USERS_TOKEN_INDEX: Lazy<Arc<RwLock<HashMap<String, String>>>> = Lazy::new(|| {
+      Arc::new(RwLock::new(HashMap::new()))
+});
+
 pub async fn update_voters_data() {
       // Get the user data
       let db_all_users = get_all_users().await;
       let db_all_users = match db_all_users {
             Ok(data) => data,
             Err(err) => {
-                  log_error("StaticData", format!("There's an error when trying to get all voters from postgres. Error: {}", err.to_string()).as_str());
+                  log_error("StaticData", format!("There's an error when trying to get all voters from postgres. Error: {}", err).as_str());
+                  set_cache_load_failed("voters").await;
                   return;
             }
       };
 
-      // Update the static users data
+      // Replace the static users data so the removed voters are gone
       {
             let mut locked_users_data = USERS_DATA.write().await;
+            let mut locked_users_token_index = USERS_TOKEN_INDEX.write().await;
+            locked_users_data.clear();
+            locked_users_token_index.clear();
             for db_user in db_all_users {
-                  locked_users_data.insert(db_user.name.clone(), db_user);
+                  locked_users_token_index.insert(db_user.token.clone(), db_user.voter_id.clone());
+                  locked_users_data.insert(db_user.voter_id.clone(), db_user);
             }
       }
 
+      set_cache_loaded("voters").await;
+
       // Log the success message
       log_something("StaticData", "Static users data successfully updated!");
 }
 
+/// Apply a single notification of the live query instead of reading the whole table again.
+pub async fn apply_voter_change(action: Action, voter: Voter) {
+      let mut locked_users_data = USERS_DATA.write().await;
+      let mut locked_users_token_index = USERS_TOKEN_INDEX.write().await;
+
+      match action {
+            Action::Create => {
+                  locked_users_token_index.insert(voter.token.clone(), voter.voter_id.clone());
+                  locked_users_data.insert(voter.voter_id.clone(), voter);
+            },
+            // The previous student number is unknown when it's changed, read everything again
+            Action::Update if locked_users_data.contains_key(&voter.voter_id) => {
+                  let new_voter_token: String = voter.token.clone();
+                  if let Some(previous_voter) = locked_users_data.insert(voter.voter_id.clone(), voter) {
+                        locked_users_token_index.remove(&previous_voter.token);
+                        locked_users_token_index.insert(new_voter_token, previous_voter.voter_id);
+                  }
+            },
+            Action::Delete => {
+                  if let Some(previous_voter) = locked_users_data.remove(&voter.voter_id) {
+                        locked_users_token_index.remove(&previous_voter.token);
+                  }
+            },
+            _ => {
+                  drop(locked_users_token_index);
+                  drop(locked_users_data);
+                  update_voters_data().await;
+            }
+      }
+}
+
 pub fn get_voters_data() -> Arc<RwLock<HashMap<String, Voter>>> {
       USERS_DATA.clone()
 }
 
+/// Get the voter of an initial token, the token may have been reset since.
+pub async fn get_voter_by_token(token: &str) -> Option<Voter> {
+      let locked_users_data = USERS_DATA.read().await;
+      let voter_id: Option<String> = USERS_TOKEN_INDEX.read().await.get(token).cloned();
+
+      voter_id.and_then(|voter_id| locked_users_data.get(&voter_id).cloned())
+}
+
+/// Several voters can share the same name.
+pub async fn get_voter_ids_by_name() -> HashMap<String, Vec<String>> {
+      let mut result: HashMap<String, Vec<String>> = HashMap::new();
+
+      for voter in USERS_DATA.read().await.values() {
+            result.entry(voter.name.clone()).or_default().push(voter.voter_id.clone());
+      }
+
+      result
+}
+
 pub async fn init_voters_data() {
       update_voters_data().await;
 }
@@ -1,22 +1,39 @@
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
-use surrealdb::Surreal;
+use surrealdb::{Surreal, Uuid};
 use surrealdb::engine::remote::ws::{Client, Ws};
 use surrealdb::method::Stream;
 use surrealdb::opt::auth::Root;
 use tokio;
 
-use crate::data::admin::update_admin_data;
-use crate::data::live_clients::get_live_clients;
-use crate::data::vote::update_votes_data;
-use crate::data::voter::update_voters_data;
-use crate::util::{log_error, log_something};
+use crate::config::{DatabaseConfig, get_config};
+use crate::data::admin::{apply_admin_change, update_admin_data};
+use crate::data::candidate::update_candidates_data;
+use crate::data::election::{get_election_message, get_results_message, is_results_embargoed, update_elections_data};
+use crate::data::live_status::{register_live_query, set_live_query_connected, set_live_query_disconnected};
+use crate::data::live_clients::{broadcast_admin_live_message, broadcast_live_message};
+use crate::data::vote::{apply_ballot_change, apply_vote_change, get_class_turnouts, get_tally_messages, get_votes_turnout, is_secret_ballot, participation_to_vote, update_votes_data};
+use crate::data::voter::{apply_voter_change, get_voters_data, update_voters_data};
+use crate::error::{AppError, AppResult};
+use crate::util::{get_timestamp, log_error, log_something};
 
 static SURREAL_DB: LazyLock<Surreal<Client>> = LazyLock::new(Surreal::init);
 
+// Unique indexes told apart when a voter insert fails
+const VOTER_ID_INDEX: &str = "voter_voter_id_unique";
+const VOTER_TOKEN_INDEX: &str = "voter_token_unique";
+
+// The caches are read again from the database this often in case a live notification was missed
+static CACHE_RECONCILIATION_INTERVAL: u64 = 5 * 60;
+// Delay in seconds before restarting a stopped live query, doubled on each failure
+static LIVE_QUERY_BACKOFF_MIN: u64 = 1;
+static LIVE_QUERY_BACKOFF_MAX: u64 = 60;
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, EnumIter, PartialEq, Eq, Hash)]
 pub enum Campus {
     MM,
@@ -27,6 +44,10 @@ impl Campus {
     pub fn as_str(&self) -> &'static str {
           campus_to_str(self)
     }
+
+    pub fn parse(campus: &str) -> Option<Campus> {
+          Campus::iter().find(|data| data.as_str().eq_ignore_ascii_case(campus.trim()))
+    }
 }
 
 pub fn campus_to_str(campus: &Campus) -> &'static str {
@@ -38,50 +59,171 @@ pub fn campus_to_str(campus: &Campus) -> &'static str {
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Voter {
+    /// The student number, the name is only used for display and lookup.
+    #[serde(default)]
+    pub voter_id: String,
     pub token: String,
     pub name: String,
     pub class: String,
     pub campus: Campus,
+    // Deactivated voters are kept for their votes but can't login anymore
+    #[serde(default)]
+    pub deactivated: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Candidate {
+    /// Votes refer to this ID so the names can be corrected.
+    #[serde(default)]
+    pub candidate_id: String,
     pub president: String,
     pub vice_president: String,
     pub campus: Campus,
     pub vision: String,
-    pub misions: Vec<String>
+    pub misions: Vec<String>,
+    // Position on the ballot of the campus
+    #[serde(default)]
+    pub position: u32,
+    // Withdrawn candidates are kept for the existing votes but can't be voted anymore
+    #[serde(default)]
+    pub withdrawn: bool
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Vote {
+    pub voter_id: String,
     pub voter_name: String,
-    pub candidate_name: String,
+    pub candidate_id: String,
     pub campus: Campus,
+    /// Empty for votes cast before receipts were introduced.
+    #[serde(default)]
+    pub ballot_id: String,
+}
+
+/// Records that a voter has voted in secret ballot mode, without the choice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Participation {
+    pub voter_id: String,
+    pub voter_name: String,
+    pub campus: Campus,
+}
+
+/// An anonymous vote in secret ballot mode. `cast_at` is rounded down to the hour.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ballot {
+    pub ballot_id: String,
+    pub candidate_id: String,
+    pub campus: Campus,
+    pub cast_at: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ElectionState {
+    #[default]
+    Draft,
+    Open,
+    Paused,
+    Closed,
+}
+
+impl ElectionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ElectionState::Draft => "Draft",
+            ElectionState::Open => "Open",
+            ElectionState::Paused => "Paused",
+            ElectionState::Closed => "Closed",
+        }
+    }
+}
+
+/// Timestamps are UNIX seconds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Election {
+    pub campus: Campus,
+    pub state: ElectionState,
+    pub starts_at: Option<i64>,
+    pub ends_at: Option<i64>,
+}
+
+impl Election {
+    pub fn new(campus: Campus) -> Election {
+        Election {
+            campus,
+            state: ElectionState::Draft,
+            starts_at: None,
+            ends_at: None,
+        }
+    }
+
+    /// The state after applying the schedule at the given time.
+    pub fn get_state_at(&self, timestamp: i64) -> ElectionState {
+        let is_started: bool = self.starts_at.is_some_and(|starts_at| starts_at <= timestamp);
+        let is_ended: bool = self.ends_at.is_some_and(|ends_at| ends_at <= timestamp);
+
+        match self.state {
+            ElectionState::Closed => ElectionState::Closed,
+            _ if is_ended => ElectionState::Closed,
+            ElectionState::Draft if is_started => ElectionState::Open,
+            state => state,
+        }
+    }
+}
+
+/// Ordered from the least to the most privileged role.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum AdminRole {
+    /// Observer, can only see the aggregated votes.
+    #[default]
+    Viewer,
+    /// Polling-station staff, can look up and reset voter tokens of their own campus.
+    Operator,
+    /// Can see every vote and manage other admins.
+    Superadmin,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Admin {
     pub admin_id: String,
+    /// Argon2 PHC string. Plaintext values from older rows are upgraded on the next successful login.
     pub admin_password: String,
-    pub admin_session_token: Option<String>,
+    #[serde(default)]
+    pub role: AdminRole,
+    /// The campus an operator is assigned to.
+    #[serde(default)]
+    pub campus: Option<Campus>,
+}
+
+impl Admin {
+    pub fn has_role(&self, role: AdminRole) -> bool {
+        self.role >= role
+    }
+
+    pub fn can_manage_campus(&self, campus: &Campus) -> bool {
+        match self.role {
+            AdminRole::Superadmin => true,
+            AdminRole::Operator => self.campus.as_ref() == Some(campus),
+            AdminRole::Viewer => false,
+        }
+    }
 }
 
 pub async fn handle_live_changes() {
     async fn voter_changes() -> surrealdb::Result<()> {
         let mut live: Stream<Vec<Voter>> = SURREAL_DB.select::<Vec<Voter>>("voter").live().await?;
+        on_live_query_started("voter").await;
 
         'notification_loop: while let Some(result) = live.next().await {
             match result {
-                Ok(_) => {
-                    update_voters_data().await;
+                Ok(notification) => {
+                    apply_voter_change(notification.action, notification.data).await;
                 }
                 Err(err) => {
                     log_error(
                         "LiveUpdate",
                         format!(
                             "There's an error when trying to get the notificaton. Error: {}",
-                            err.to_string()
+                            err
                         )
                         .as_str(),
                     );
@@ -97,18 +239,19 @@ pub async fn handle_live_changes() {
 
     async fn admin_changes() -> surrealdb::Result<()> {
         let mut live: Stream<Vec<Admin>> = SURREAL_DB.select::<Vec<Admin>>("admin").live().await?;
+        on_live_query_started("admin").await;
 
         'notification_loop: while let Some(result) = live.next().await {
             match result {
-                Ok(_) => {
-                    update_admin_data().await;
+                Ok(notification) => {
+                    apply_admin_change(notification.action, notification.data).await;
                 }
                 Err(err) => {
                     log_error(
                         "LiveUpdate",
                         format!(
                             "There's an error when trying to get the notificaton. Error: {}",
-                            err.to_string()
+                            err
                         )
                         .as_str(),
                     );
@@ -122,16 +265,69 @@ pub async fn handle_live_changes() {
         Ok(())
     }
 
+    async fn candidate_changes() -> surrealdb::Result<()> {
+        let mut live: Stream<Vec<Candidate>> = SURREAL_DB.select::<Vec<Candidate>>("candidate").live().await?;
+        on_live_query_started("candidate").await;
+
+        'notification_loop: while let Some(result) = live.next().await {
+            match result {
+                Ok(_) => {
+                    update_candidates_data().await;
+                }
+                Err(err) => {
+                    log_error(
+                        "LiveUpdate",
+                        format!(
+                            "There's an error when trying to get the notificaton. Error: {}",
+                            err
+                        )
+                        .as_str(),
+                    );
+                    continue 'notification_loop;
+                }
+            };
+
+            log_something("LiveUpdate", "There's an update for candidate data!");
+        }
+
+        Ok(())
+    }
+
+    // Only the turnout is sent while the results are embargoed
+    async fn broadcast_tally(campus: &Campus) {
+        if is_results_embargoed(campus).await {
+            return;
+        }
+
+        for message in get_tally_messages(campus).await {
+            broadcast_live_message(message.as_str()).await;
+        }
+    }
+
+    async fn broadcast_turnout(campus: &Campus, voter_id: &str) {
+        broadcast_live_message(format!("t:{},{}", campus.as_str(), get_votes_turnout(campus).await).as_str()).await;
+
+        let voter_class: Option<String> = get_voters_data().read().await
+            .get(voter_id)
+            .map(|voter| voter.class.clone());
+        if let Some(voter_class) = voter_class {
+            let class_turnout: usize = get_class_turnouts(campus).await.get(&voter_class).copied().unwrap_or(0);
+            broadcast_live_message(format!("tc:{},{},{}", campus.as_str(), voter_class, class_turnout).as_str()).await;
+        }
+    }
+
     async fn votes_changes() -> surrealdb::Result<()> {
         let mut live: Stream<Vec<Vote>> = SURREAL_DB.select::<Vec<Vote>>("vote").live().await?;
+        on_live_query_started("vote").await;
 
         'notification_loop: while let Some(result) = live.next().await {
             match result {
                 Ok(notification) => {
-                    update_votes_data().await;
-                    let live_clients = get_live_clients();
-                    let mut locked_write_live_clients = live_clients.write().await;
+                    if !is_secret_ballot() {
+                        apply_vote_change(notification.action, notification.data.clone()).await;
+                    }
 
+                    // Send who voted for whom to the superadmins
                     let mut message: String = String::from("v");
 
                     message += match notification.action {
@@ -143,18 +339,20 @@ pub async fn handle_live_changes() {
 
                     message += notification.data.voter_name.as_str();
                     message += ",";
-                    message += notification.data.candidate_name.as_str();
+                    message += notification.data.candidate_id.as_str();
 
-                    for (_, session) in locked_write_live_clients.iter_mut() {
-                        let _ = session.text(message.as_str()).await;
-                    }
+                    broadcast_admin_live_message(message.as_str()).await;
+
+                    // Send the aggregated counts to the public
+                    broadcast_tally(&notification.data.campus).await;
+                    broadcast_turnout(&notification.data.campus, notification.data.voter_id.as_str()).await;
                 }
                 Err(err) => {
                     log_error(
                         "LiveUpdate",
                         format!(
                             "There's an error when trying to get the notificaton. Error: {}",
-                            err.to_string()
+                            err
                         )
                         .as_str(),
                     );
@@ -168,148 +366,658 @@ pub async fn handle_live_changes() {
         Ok(())
     }
 
-    tokio::spawn(async {
-        let result = voter_changes().await;
-        match result {
-            Ok(_) => (),
-            Err(err) => {
-                log_error(
-                    "LiveUpdate",
-                    format!(
-                        "There's an error when using live select for voter database! Error: {}",
-                        err.to_string()
-                    )
-                    .as_str(),
-                );
+    async fn participation_changes() -> surrealdb::Result<()> {
+        let mut live: Stream<Vec<Participation>> = SURREAL_DB.select::<Vec<Participation>>("participation").live().await?;
+        on_live_query_started("participation").await;
+
+        'notification_loop: while let Some(result) = live.next().await {
+            match result {
+                Ok(notification) => {
+                    apply_vote_change(notification.action, participation_to_vote(notification.data.clone())).await;
+
+                    // Only the turnout is sent, a voter name next to the following ballot would link them together
+                    broadcast_turnout(&notification.data.campus, notification.data.voter_id.as_str()).await;
+                }
+                Err(err) => {
+                    log_error(
+                        "LiveUpdate",
+                        format!(
+                            "There's an error when trying to get the notificaton. Error: {}",
+                            err
+                        )
+                        .as_str(),
+                    );
+                    continue 'notification_loop;
+                }
             }
+
+            log_something("LiveUpdate", "There's an update for participation data!");
         }
-    });
 
-    tokio::spawn(async {
-        let result = admin_changes().await;
-        match result {
-            Ok(_) => (),
-            Err(err) => {
-                log_error(
-                    "LiveUpdate",
-                    format!(
-                        "There's an error when using live select for admin database! Error: {}",
-                        err.to_string()
-                    )
-                    .as_str(),
-                );
+        Ok(())
+    }
+
+    async fn ballot_changes() -> surrealdb::Result<()> {
+        let mut live: Stream<Vec<Ballot>> = SURREAL_DB.select::<Vec<Ballot>>("ballot").live().await?;
+        on_live_query_started("ballot").await;
+
+        'notification_loop: while let Some(result) = live.next().await {
+            match result {
+                Ok(notification) => {
+                    let campus: Campus = notification.data.campus;
+                    apply_ballot_change(notification.action, notification.data).await;
+                    broadcast_tally(&campus).await;
+                }
+                Err(err) => {
+                    log_error(
+                        "LiveUpdate",
+                        format!(
+                            "There's an error when trying to get the notificaton. Error: {}",
+                            err
+                        )
+                        .as_str(),
+                    );
+                    continue 'notification_loop;
+                }
             }
+
+            log_something("LiveUpdate", "There's an update for ballot data!");
         }
-    });
 
+        Ok(())
+    }
+
+    async fn election_changes() -> surrealdb::Result<()> {
+        let mut live: Stream<Vec<Election>> = SURREAL_DB.select::<Vec<Election>>("election").live().await?;
+        on_live_query_started("election").await;
+
+        'notification_loop: while let Some(result) = live.next().await {
+            match result {
+                Ok(notification) => {
+                    update_elections_data().await;
+
+                    if notification.action != surrealdb::Action::Delete {
+                        broadcast_live_message(get_election_message(&notification.data).as_str()).await;
+                    }
+
+                    // Release the results once the election is closed
+                    if get_config().election.results_embargo && notification.data.state == ElectionState::Closed {
+                        broadcast_live_message(get_results_message(&notification.data.campus).await.as_str()).await;
+                    }
+                }
+                Err(err) => {
+                    log_error(
+                        "LiveUpdate",
+                        format!(
+                            "There's an error when trying to get the notificaton. Error: {}",
+                            err
+                        )
+                        .as_str(),
+                    );
+                    continue 'notification_loop;
+                }
+            }
+
+            log_something("LiveUpdate", "There's an update for election data!");
+        }
+
+        Ok(())
+    }
+
+    supervise_live_query("voter", voter_changes);
+    supervise_live_query("admin", admin_changes);
+    supervise_live_query("candidate", candidate_changes);
+    supervise_live_query("vote", votes_changes);
+
+    if is_secret_ballot() {
+        supervise_live_query("participation", participation_changes);
+        supervise_live_query("ballot", ballot_changes);
+    }
+
+    supervise_live_query("election", election_changes);
+
+    spawn_cache_reconciliation();
+}
+
+/// Checks the connection and the session, `INFO FOR DB` fails when no namespace or database is used.
+pub async fn ping_db() -> AppResult<()> {
+    SURREAL_DB
+        .query("INFO FOR DB")
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+async fn resync_static_data() {
+    update_voters_data().await;
+    update_admin_data().await;
+    update_candidates_data().await;
+    update_votes_data().await;
+    update_elections_data().await;
+
+    log_something("LiveUpdate", "The static data is reconciled with the database.");
+}
+
+/// Read every cache again from the database as a safety net for the incremental updates.
+fn spawn_cache_reconciliation() {
     tokio::spawn(async {
-        let result = votes_changes().await;
-        match result {
-            Ok(_) => (),
-            Err(err) => {
-                log_error(
-                    "LiveUpdate",
-                    format!(
-                        "There's an error when using live select for votes database! Error: {}",
-                        err.to_string()
-                    )
-                    .as_str(),
-                );
+        let mut interval = tokio::time::interval(Duration::from_secs(CACHE_RECONCILIATION_INTERVAL));
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            resync_static_data().await;
+        }
+    });
+}
+
+/// Changes made while the live query was down are missed, so everything is read again after a restart.
+async fn on_live_query_started(table: &'static str) {
+    log_something("LiveUpdate", format!("The live query of {} is connected.", table).as_str());
+
+    if set_live_query_connected(table).await {
+        resync_static_data().await;
+    }
+}
+
+/// Keep restarting the live query with an exponential backoff whenever it stops.
+fn supervise_live_query<F, Fut>(table: &'static str, live_query: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = surrealdb::Result<()>> + Send,
+{
+    tokio::spawn(async move {
+        register_live_query(table).await;
+        let mut backoff: u64 = LIVE_QUERY_BACKOFF_MIN;
+
+        loop {
+            let error: String = match live_query().await {
+                Ok(_) => String::from("The live query has ended"),
+                Err(err) => err.to_string(),
+            };
+
+            log_error(
+                "LiveUpdate",
+                format!(
+                    "The live query of {} has stopped, restarting in {} seconds. Error: {}",
+                    table, backoff, error
+                )
+                .as_str(),
+            );
+
+            // Start over when the live query was working before it stopped
+            if set_live_query_disconnected(table).await {
+                backoff = LIVE_QUERY_BACKOFF_MIN;
             }
+
+            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            backoff = (backoff * 2).min(LIVE_QUERY_BACKOFF_MAX);
         }
     });
 }
 
-pub async fn init_db() {
+pub async fn init_db() -> Result<(), surrealdb::Error> {
+    let database_config: &DatabaseConfig = &get_config().database;
+
     SURREAL_DB
-        .connect::<Ws>(std::env::var("DATABASE_URL").unwrap())
-        .await
-        .unwrap();
+        .connect::<Ws>(database_config.url.as_str())
+        .await?;
 
     SURREAL_DB
         .signin(Root {
-            username: std::env::var("SURREAL_USERNAME").unwrap().as_str(),
-            password: std::env::var("SURREAL_PASSWORD").unwrap().as_str(),
+            username: database_config.username.as_str(),
+            password: database_config.password.as_str(),
         })
-        .await
-        .unwrap();
+        .await?;
 
     SURREAL_DB
-        .use_ns(std::env::var("SURREAL_NS_NAME").unwrap())
-        .use_db(std::env::var("SURREAL_DB_NAME").unwrap())
-        .await
-        .unwrap();
+        .use_ns(database_config.namespace.as_str())
+        .use_db(database_config.name.as_str())
+        .await?;
+
+    migrate_candidate_ids().await;
+    migrate_voter_ids().await;
+    migrate_admin_roles().await;
+    define_indexes().await?;
 
     handle_live_changes().await;
+
+    Ok(())
+}
+
+/// Whether any row of the tables is still missing the column, the migrations are skipped on a migrated database.
+async fn has_unmigrated_rows(tables: &str, column: &str) -> bool {
+    let count_result: Result<Option<usize>, surrealdb::Error> = match SURREAL_DB
+        .query(format!("RETURN array::len(SELECT VALUE id FROM {} WHERE {} = NONE LIMIT 1)", tables, column))
+        .await
+    {
+        Ok(mut response) => response.take::<Option<usize>>(0),
+        Err(err) => Err(err),
+    };
+
+    match count_result {
+        Ok(count) => count.unwrap_or(0) > 0,
+        Err(err) => {
+            log_error("Database", format!("There's an error when trying to count the rows without {}, migrating them anyway. Error: {}", column, err).as_str());
+            true
+        }
+    }
+}
+
+/// Give every candidate an ID and point the votes cast by president name to it. Only rows without an ID are touched,
+/// votes of an unknown candidate keep the old name as their ID.
+async fn migrate_candidate_ids() {
+    if !has_unmigrated_rows("candidate, vote, ballot", "candidate_id").await {
+        return;
+    }
+
+    let migrate_result = SURREAL_DB
+        .query("BEGIN TRANSACTION")
+        .query("UPDATE candidate SET candidate_id = <string> rand::uuid::v4() WHERE candidate_id = NONE")
+        .query("FOR $candidate IN (SELECT president, candidate_id FROM candidate) {
+            UPDATE vote SET candidate_id = $candidate.candidate_id, candidate_name = NONE WHERE candidate_id = NONE AND candidate_name = $candidate.president;
+            UPDATE ballot SET candidate_id = $candidate.candidate_id, candidate_name = NONE WHERE candidate_id = NONE AND candidate_name = $candidate.president;
+        }")
+        .query("UPDATE vote SET candidate_id = candidate_name, candidate_name = NONE WHERE candidate_id = NONE")
+        .query("UPDATE ballot SET candidate_id = candidate_name, candidate_name = NONE WHERE candidate_id = NONE")
+        .query("COMMIT TRANSACTION")
+        .await;
+
+    match migrate_result.map(|mut response| response.take_errors()) {
+        Ok(errors) if errors.is_empty() => log_something("Database", "Candidate IDs are migrated."),
+        Ok(errors) => {
+            for err in errors.values() {
+                log_error("Database", format!("There's an error when trying to migrate the candidate IDs. Error: {}", err).as_str());
+            }
+        }
+        Err(err) => log_error("Database", format!("There's an error when trying to migrate the candidate IDs. Error: {}", err).as_str()),
+    }
 }
 
-pub async fn get_all_users() -> surrealdb::Result<Vec<Voter>> {
-    SURREAL_DB.select::<Vec<Voter>>("voter").await
+/// Admins from before the roles had every permission, so they become superadmins instead of viewers.
+async fn migrate_admin_roles() {
+    let migrate_result = SURREAL_DB
+        .query("UPDATE admin SET role = 'Superadmin' WHERE role = NONE")
+        .await;
+
+    match migrate_result.map(|mut response| response.take_errors()) {
+        Ok(errors) if errors.is_empty() => log_something("Database", "Admin roles are migrated."),
+        Ok(errors) => {
+            for err in errors.values() {
+                log_error("Database", format!("There's an error when trying to migrate the admin roles. Error: {}", err).as_str());
+            }
+        }
+        Err(err) => log_error("Database", format!("There's an error when trying to migrate the admin roles. Error: {}", err).as_str()),
+    }
 }
 
-pub async fn get_user_by_token(token: String) -> surrealdb::Result<Option<Voter>> {
+/// Use the record ID of the voters without a student number and point their votes to it. When several voters share
+/// a name, the vote goes to the first of them as the name was the only key before.
+async fn migrate_voter_ids() {
+    if !has_unmigrated_rows("voter, vote, participation", "voter_id").await {
+        return;
+    }
+
+    let migrate_result = SURREAL_DB
+        .query("BEGIN TRANSACTION")
+        .query("UPDATE voter SET voter_id = <string> record::id(id) WHERE voter_id = NONE")
+        .query("FOR $voter IN (SELECT name, voter_id FROM voter) {
+            UPDATE vote SET voter_id = $voter.voter_id WHERE voter_id = NONE AND voter_name = $voter.name;
+            UPDATE participation SET voter_id = $voter.voter_id WHERE voter_id = NONE AND voter_name = $voter.name;
+        }")
+        .query("UPDATE vote SET voter_id = voter_name WHERE voter_id = NONE")
+        .query("UPDATE participation SET voter_id = voter_name WHERE voter_id = NONE")
+        .query("REMOVE INDEX IF EXISTS vote_voter_name_unique ON TABLE vote")
+        .query("REMOVE INDEX IF EXISTS participation_voter_name_unique ON TABLE participation")
+        .query("COMMIT TRANSACTION")
+        .await;
+
+    match migrate_result.map(|mut response| response.take_errors()) {
+        Ok(errors) if errors.is_empty() => log_something("Database", "Voter IDs are migrated."),
+        Ok(errors) => {
+            for err in errors.values() {
+                log_error("Database", format!("There's an error when trying to migrate the voter IDs. Error: {}", err).as_str());
+            }
+        }
+        Err(err) => log_error("Database", format!("There's an error when trying to migrate the voter IDs. Error: {}", err).as_str()),
+    }
+}
+
+/// A voter can only have one vote or participation and no two voters share a token, enforced by the database across
+/// every API instance. The API doesn't start without them, they fail to be defined when there are duplicates.
+async fn define_indexes() -> Result<(), surrealdb::Error> {
+    let define_result = SURREAL_DB
+        .query(format!("DEFINE INDEX IF NOT EXISTS {} ON TABLE voter COLUMNS voter_id UNIQUE", VOTER_ID_INDEX))
+        .query(format!("DEFINE INDEX IF NOT EXISTS {} ON TABLE voter COLUMNS token UNIQUE", VOTER_TOKEN_INDEX))
+        .query("DEFINE INDEX IF NOT EXISTS vote_voter_id_unique ON TABLE vote COLUMNS voter_id UNIQUE")
+        .query("DEFINE INDEX IF NOT EXISTS participation_voter_id_unique ON TABLE participation COLUMNS voter_id UNIQUE")
+        .query("DEFINE INDEX IF NOT EXISTS ballot_ballot_id_unique ON TABLE ballot COLUMNS ballot_id UNIQUE")
+        .query("DEFINE INDEX IF NOT EXISTS candidate_candidate_id_unique ON TABLE candidate COLUMNS candidate_id UNIQUE")
+        .await
+        .and_then(surrealdb::Response::check);
+
+    match define_result {
+        Ok(_) => {
+            log_something("Database", "Unique indexes are defined.");
+            Ok(())
+        }
+        Err(err) => {
+            log_error(
+                "Database",
+                format!(
+                    "There's an error when trying to define the unique indexes, check for duplicate votes or voter tokens. Error: {}",
+                    err
+                )
+                .as_str(),
+            );
+            Err(err)
+        }
+    }
+}
+
+/// The server only sends the message of a failed statement over the WebSocket, the typed error is only
+/// returned by an embedded engine.
+fn get_violated_unique_index(err: &surrealdb::Error) -> Option<&str> {
+    match err {
+        surrealdb::Error::Db(surrealdb::error::Db::IndexExists { index, .. }) => Some(index.as_str()),
+        surrealdb::Error::Api(surrealdb::error::Api::Query(message)) => message
+            .strip_prefix("Database index `")
+            .and_then(|message| message.split_once("` already contains "))
+            .map(|(index, _)| index),
+        _ => None,
+    }
+}
+
+fn is_unique_index_violation(err: &surrealdb::Error) -> bool {
+    get_violated_unique_index(err).is_some()
+}
+
+/// Whether the voters weren't inserted because another voter was given one of their tokens first.
+pub fn is_voter_token_conflict(err: &AppError) -> bool {
+    match err {
+        AppError::Database(err) => get_violated_unique_index(err) == Some(VOTER_TOKEN_INDEX),
+        _ => false,
+    }
+}
+
+fn check_voters_response(mut response: surrealdb::Response) -> AppResult<()> {
+    let errors = response.take_errors();
+
+    if errors.values().any(|err| get_violated_unique_index(err) == Some(VOTER_ID_INDEX)) {
+        return Err(AppError::Conflict(String::from("The student number is already used by another voter.")));
+    }
+
+    // A token conflict is kept as a database error so the caller can retry with new tokens
+    match errors.into_iter().min_by_key(|(index, _)| *index) {
+        Some((_, err)) => Err(AppError::from(err)),
+        None => Ok(()),
+    }
+}
+
+fn check_vote_response(mut response: surrealdb::Response) -> AppResult<()> {
+    let errors = response.take_errors();
+
+    // Unique index violations means another request has inserted the vote first
+    if errors.values().any(is_unique_index_violation) {
+        return Err(AppError::Conflict(String::from("The voter has already voted.")));
+    }
+
+    match errors.into_iter().min_by_key(|(index, _)| *index) {
+        Some((_, err)) => Err(AppError::from(err)),
+        None => Ok(()),
+    }
+}
+
+pub async fn get_all_users() -> AppResult<Vec<Voter>> {
+    Ok(SURREAL_DB.select::<Vec<Voter>>("voter").await?)
+}
+
+pub async fn get_user_by_token(token: String) -> AppResult<Option<Voter>> {
     let result = SURREAL_DB
         .query("SELECT * FROM voter WHERE token = $token")
         .bind(("token", token))
         .await?
         .take::<Vec<Voter>>(0)?;
 
-    Ok(result.get(0).map(|voter_data| voter_data.clone()))
+    Ok(result.first().cloned())
+}
+
+/// Insert every voter or none of them.
+pub async fn insert_voters(voters: Vec<Voter>) -> AppResult<()> {
+    let response = SURREAL_DB
+        .query("BEGIN TRANSACTION; INSERT INTO voter $voters; COMMIT TRANSACTION;")
+        .bind(("voters", voters))
+        .await?;
+    check_voters_response(response)
 }
 
-pub async fn get_all_candidates() -> surrealdb::Result<Vec<Candidate>> {
-    SURREAL_DB.select::<Vec<Candidate>>("candidate").await
+pub async fn update_voter(voter: Voter) -> AppResult<()> {
+    SURREAL_DB
+        .query("UPDATE voter CONTENT $voter WHERE voter_id = $voter_id")
+        .bind(("voter_id", voter.voter_id.clone()))
+        .bind(("voter", voter))
+        .await?
+        .check()?;
+
+    Ok(())
 }
 
-pub async fn get_all_votes(campus: Option<Campus>) -> surrealdb::Result<Vec<Vote>> {
-    match campus {
+pub async fn deactivate_voter(voter_id: impl Into<String>) -> AppResult<()> {
+    SURREAL_DB
+        .query("UPDATE voter SET deactivated = true WHERE voter_id = $voter_id")
+        .bind(("voter_id", voter_id.into()))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn get_all_candidates() -> AppResult<Vec<Candidate>> {
+    Ok(SURREAL_DB.select::<Vec<Candidate>>("candidate").await?)
+}
+
+pub async fn insert_candidate(candidate: Candidate) -> AppResult<()> {
+    SURREAL_DB
+        .query("CREATE candidate CONTENT $candidate")
+        .bind(("candidate", candidate))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn update_candidate(candidate: Candidate) -> AppResult<()> {
+    SURREAL_DB
+        .query("UPDATE candidate CONTENT $candidate WHERE candidate_id = $candidate_id")
+        .bind(("candidate_id", candidate.candidate_id.clone()))
+        .bind(("candidate", candidate))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+/// Set the ballot position of each candidate following the order of `candidate_ids`.
+pub async fn reorder_candidates(candidate_ids: Vec<String>) -> AppResult<()> {
+    SURREAL_DB
+        .query("BEGIN TRANSACTION; FOR $candidate_id IN $candidate_ids { UPDATE candidate SET position = array::find_index($candidate_ids, $candidate_id) WHERE candidate_id = $candidate_id; }; COMMIT TRANSACTION;")
+        .bind(("candidate_ids", candidate_ids))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn withdraw_candidate(candidate_id: impl Into<String>) -> AppResult<()> {
+    SURREAL_DB
+        .query("UPDATE candidate SET withdrawn = true WHERE candidate_id = $candidate_id")
+        .bind(("candidate_id", candidate_id.into()))
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+pub async fn get_all_votes(campus: Option<Campus>) -> AppResult<Vec<Vote>> {
+    let result = match campus {
         Some(campus) => SURREAL_DB
             .query("SELECT * FROM vote WHERE campus = $campus")
             .bind(("campus", campus))
             .await?
-            .take::<Vec<Vote>>(0),
-        None => SURREAL_DB.select::<Vec<Vote>>("vote").await,
-    }
+            .take::<Vec<Vote>>(0)?,
+        None => SURREAL_DB.select::<Vec<Vote>>("vote").await?,
+    };
+
+    Ok(result)
 }
 
 pub async fn insert_vote(
+    voter_id: String,
     voter_name: String,
-    candidate_name: String,
+    candidate_id: String,
     campus: Campus,
-) -> surrealdb::Result<()> {
-    SURREAL_DB
-        .insert::<Vec<Vote>>("vote")
-        .content(vec![Vote {
-            voter_name: voter_name,
-            candidate_name: candidate_name,
-            campus: campus,
-        }])
+) -> AppResult<Vote> {
+    let vote: Vote = Vote {
+        voter_id,
+        voter_name,
+        candidate_id,
+        campus,
+        ballot_id: Uuid::new_v4().to_string(),
+    };
+
+    let response = SURREAL_DB
+        .query("BEGIN TRANSACTION; CREATE vote CONTENT $vote; COMMIT TRANSACTION;")
+        .bind(("vote", vote.clone()))
         .await?;
+    check_vote_response(response)?;
 
-    Ok(())
+    Ok(vote)
+}
+
+/// Get the candidate of a ballot from either the vote or the secret ballot table.
+pub async fn get_candidate_by_ballot_id(ballot_id: String, is_secret_ballot: bool) -> AppResult<Option<String>> {
+    let query: &str = if is_secret_ballot {
+        "SELECT VALUE candidate_id FROM ballot WHERE ballot_id = $ballot_id"
+    } else {
+        "SELECT VALUE candidate_id FROM vote WHERE ballot_id = $ballot_id"
+    };
+
+    let result = SURREAL_DB
+        .query(query)
+        .bind(("ballot_id", ballot_id))
+        .await?
+        .take::<Vec<String>>(0)?;
+
+    Ok(result.first().cloned())
 }
 
-pub async fn remove_vote(voter_name: String) -> surrealdb::Result<()> {
+pub async fn remove_vote(voter_id: String) -> AppResult<()> {
     SURREAL_DB
-        .query("DELETE FROM vote WHERE voter_name = $voter_name")
-        .bind(("voter_name", voter_name))
-        .await?;
+        .query("DELETE FROM vote WHERE voter_id = $voter_id")
+        .bind(("voter_id", voter_id))
+        .await?
+        .check()?;
 
     Ok(())
 }
 
-pub async fn get_all_admins() -> surrealdb::Result<Vec<Admin>> {
-    SURREAL_DB.select::<Vec<Admin>>("admin").await
+pub async fn get_all_participations(campus: Option<Campus>) -> AppResult<Vec<Participation>> {
+    let result = match campus {
+        Some(campus) => SURREAL_DB
+            .query("SELECT * FROM participation WHERE campus = $campus")
+            .bind(("campus", campus))
+            .await?
+            .take::<Vec<Participation>>(0)?,
+        None => SURREAL_DB.select::<Vec<Participation>>("participation").await?,
+    };
+
+    Ok(result)
+}
+
+pub async fn get_all_ballots(campus: Option<Campus>) -> AppResult<Vec<Ballot>> {
+    let result = match campus {
+        Some(campus) => SURREAL_DB
+            .query("SELECT * FROM ballot WHERE campus = $campus")
+            .bind(("campus", campus))
+            .await?
+            .take::<Vec<Ballot>>(0)?,
+        None => SURREAL_DB.select::<Vec<Ballot>>("ballot").await?,
+    };
+
+    Ok(result)
+}
+
+/// Record the participation and the anonymous ballot in one transaction.
+pub async fn insert_secret_vote(
+    voter_id: String,
+    voter_name: String,
+    candidate_id: String,
+    campus: Campus,
+) -> AppResult<Ballot> {
+    let ballot: Ballot = Ballot {
+        ballot_id: Uuid::new_v4().to_string(),
+        candidate_id,
+        campus,
+        cast_at: get_timestamp() / 3600 * 3600,
+    };
+
+    let response = SURREAL_DB
+        .query("BEGIN TRANSACTION; CREATE participation CONTENT $participation; CREATE ballot CONTENT $ballot; COMMIT TRANSACTION;")
+        .bind(("participation", Participation { voter_id, voter_name, campus }))
+        .bind(("ballot", ballot.clone()))
+        .await?;
+    check_vote_response(response)?;
+
+    Ok(ballot)
+}
+
+pub async fn get_all_admins() -> AppResult<Vec<Admin>> {
+    Ok(SURREAL_DB.select::<Vec<Admin>>("admin").await?)
 }
 
-pub async fn set_admin_session_token(
+pub async fn set_admin_password(
     admin_id: impl Into<String>,
-    admin_session_token: impl Into<String>,
-) -> surrealdb::Result<()> {
-    SURREAL_DB.query("UPDATE admin SET admin_session_token = $admin_session_token WHERE admin_id = $admin_id")
-            .bind(("admin_session_token", admin_session_token.into()))
+    admin_password: impl Into<String>,
+) -> AppResult<()> {
+    SURREAL_DB.query("UPDATE admin SET admin_password = $admin_password WHERE admin_id = $admin_id")
+            .bind(("admin_password", admin_password.into()))
+            .bind(("admin_id", admin_id.into()))
+            .await?
+            .check()?;
+
+    Ok(())
+}
+
+pub async fn upsert_admin(admin: Admin) -> AppResult<()> {
+    SURREAL_DB.query("BEGIN TRANSACTION; DELETE FROM admin WHERE admin_id = $admin_id; CREATE admin CONTENT $admin; COMMIT TRANSACTION;")
+            .bind(("admin_id", admin.admin_id.clone()))
+            .bind(("admin", admin))
+            .await?
+            .check()?;
+
+    Ok(())
+}
+
+pub async fn remove_admin(admin_id: impl Into<String>) -> AppResult<()> {
+    SURREAL_DB.query("DELETE FROM admin WHERE admin_id = $admin_id")
             .bind(("admin_id", admin_id.into()))
-            .await?;
+            .await?
+            .check()?;
+
+    Ok(())
+}
+
+pub async fn get_all_elections() -> AppResult<Vec<Election>> {
+    Ok(SURREAL_DB.select::<Vec<Election>>("election").await?)
+}
+
+pub async fn upsert_election(election: Election) -> AppResult<()> {
+    SURREAL_DB.query("BEGIN TRANSACTION; DELETE FROM election WHERE campus = $campus; CREATE election CONTENT $election; COMMIT TRANSACTION;")
+            .bind(("campus", election.campus))
+            .bind(("election", election))
+            .await?
+            .check()?;
 
     Ok(())
 }
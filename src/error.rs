@@ -0,0 +1,138 @@
+use std::fmt;
+
+use actix_web::{HttpResponse, ResponseError, http::{StatusCode, header}};
+use deadpool_redis::PoolError;
+use redis::RedisError;
+use serde::Serialize;
+
+tokio::task_local! {
+      /// The ID of the request being handled, set by the request middleware.
+      pub static REQUEST_ID: String;
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+#[derive(Debug)]
+pub enum AppError {
+      Unauthorized,
+      Forbidden,
+      NotFound,
+      Conflict(String),
+      ElectionClosed,
+      Validation(String),
+      // Seconds until the lockout ends
+      TooManyRequests(i64),
+      Database(Box<surrealdb::Error>),
+      Redis(Box<RedisError>),
+      RedisPool(Box<PoolError>),
+      Serialization(serde_json::Error),
+      Internal(String)
+}
+
+#[derive(Serialize)]
+struct AppErrorResponseType {
+      code: &'static str,
+      message: String,
+      request_id: Option<String>
+}
+
+impl AppError {
+      /// Stable identifier of the error for the clients.
+      pub fn code(&self) -> &'static str {
+            match self {
+                  AppError::Unauthorized => "unauthorized",
+                  AppError::Forbidden => "forbidden",
+                  AppError::NotFound => "not_found",
+                  AppError::Conflict(_) => "conflict",
+                  AppError::ElectionClosed => "election_closed",
+                  AppError::Validation(_) => "validation_failed",
+                  AppError::TooManyRequests(_) => "too_many_requests",
+                  AppError::Database(_) => "database_error",
+                  AppError::Redis(_) | AppError::RedisPool(_) => "redis_error",
+                  AppError::Serialization(_) | AppError::Internal(_) => "internal_error",
+            }
+      }
+
+      /// The message sent to the clients, the details of the server errors are only logged.
+      pub fn message(&self) -> String {
+            match self {
+                  AppError::Unauthorized => String::from("The session is missing or invalid."),
+                  AppError::Forbidden => String::from("The session isn't allowed to do this."),
+                  AppError::NotFound => String::from("The resource doesn't exist."),
+                  AppError::Conflict(message) | AppError::Validation(message) => message.clone(),
+                  AppError::ElectionClosed => String::from("The election isn't open."),
+                  AppError::TooManyRequests(_) => String::from("Too many failed attempts, try again later."),
+                  AppError::Database(_) | AppError::Redis(_) | AppError::RedisPool(_) | AppError::Serialization(_) | AppError::Internal(_) => String::from("Internal server error."),
+            }
+      }
+}
+
+impl fmt::Display for AppError {
+      fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                  AppError::Database(err) => write!(f, "{}", err),
+                  AppError::Redis(err) => write!(f, "{}", err),
+                  AppError::RedisPool(err) => write!(f, "{}", err),
+                  AppError::Serialization(err) => write!(f, "{}", err),
+                  AppError::Internal(message) => write!(f, "{}", message),
+                  _ => write!(f, "{}", self.message()),
+            }
+      }
+}
+
+impl ResponseError for AppError {
+      fn status_code(&self) -> StatusCode {
+            match self {
+                  AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+                  AppError::Forbidden | AppError::ElectionClosed => StatusCode::FORBIDDEN,
+                  AppError::NotFound => StatusCode::NOT_FOUND,
+                  AppError::Conflict(_) => StatusCode::CONFLICT,
+                  AppError::Validation(_) => StatusCode::BAD_REQUEST,
+                  AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+                  AppError::Database(_) | AppError::Redis(_) | AppError::RedisPool(_) | AppError::Serialization(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            }
+      }
+
+      fn error_response(&self) -> HttpResponse {
+            let mut response = HttpResponse::build(self.status_code());
+            if let AppError::TooManyRequests(retry_after) = self {
+                  response.insert_header((header::RETRY_AFTER, retry_after.to_string()));
+            }
+
+            response.json(AppErrorResponseType {
+                  code: self.code(),
+                  message: self.message(),
+                  request_id: REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+            })
+      }
+}
+
+impl From<surrealdb::Error> for AppError {
+      fn from(err: surrealdb::Error) -> Self {
+            AppError::Database(Box::new(err))
+      }
+}
+
+impl From<RedisError> for AppError {
+      fn from(err: RedisError) -> Self {
+            AppError::Redis(Box::new(err))
+      }
+}
+
+impl From<PoolError> for AppError {
+      fn from(err: PoolError) -> Self {
+            AppError::RedisPool(Box::new(err))
+      }
+}
+
+impl From<argon2::password_hash::Error> for AppError {
+      fn from(err: argon2::password_hash::Error) -> Self {
+            AppError::Internal(err.to_string())
+      }
+}
+
+impl From<serde_json::Error> for AppError {
+      fn from(err: serde_json::Error) -> Self {
+            AppError::Serialization(err)
+      }
+}
@@ -4,3 +4,6 @@ pub mod util;
 pub mod data;
 pub mod db;
 pub mod rdb;
+pub mod auth;
+pub mod error;
+pub mod config;
@@ -1,60 +1,113 @@
 use actix_web::{App, HttpServer, middleware::from_fn, web};
-use actix_cors::Cors;
-use deadpool_redis::{Config as RedisConfig, Runtime as RedisRuntime};
+use deadpool_redis::{Config as RedisConfig, PoolConfig as RedisPoolConfig, Runtime as RedisRuntime};
 use kprs_web_api::{
-    data::{admin::init_admin_data, candidate::init_candidates_data, vote::init_votes_count, voter::init_voters_data},
+    config::{Config, get_config, init_config},
+    data::{admin::init_admin_data, candidate::init_candidates_data, cluster::spawn_leader_election, election::init_elections_data, live_clients::init_live_broadcast, vote::init_votes_count, voter::{get_voter_ids_by_name, init_voters_data}},
     db::init_db,
-    middleware::middleware,
+    rdb::{index_voters_data_redis, migrate_redis_values, migrate_voters_data_redis},
+    middleware::{cors, json_config, middleware, path_config, query_config},
     routes::{
-        admin::{admin_check_api, admin_login_api, admin_reset_api, admin_token_api, admin_votes_api, admin_votes_simple_api},
+        admin::{admin_admins_delete_api, admin_candidates_delete_api, admin_candidates_get_api, admin_candidates_order_api, admin_candidates_post_api, admin_candidates_update_api, admin_election_schedule_api, admin_election_state_api, admin_admins_get_api, admin_admins_post_api, admin_check_api, admin_lockouts_delete_api, admin_lockouts_get_api, admin_login_api, admin_logout_all_api, admin_logout_api, admin_reset_api, admin_token_api, admin_voters_delete_api, admin_voters_get_api, admin_voters_import_api, admin_voters_post_api, admin_voters_update_api, admin_votes_api, admin_votes_simple_api},
         candidate::candidate_get_api,
+        election::election_get_api,
+        health::{healthz_get_api, readyz_get_api},
+        receipt::receipt_get_api,
         voter::{voter_check_api, voter_get_api, voter_logout_api, voter_vote_api},
-        ws::live_votes_data
+        ws::{admin_live_votes_data, live_votes_data}
     },
-    util::log_something
+    util::{log_error, log_something}
 };
 
 
+fn startup_error(message: String) -> std::io::Error {
+    log_error("Setup", message.as_str());
+    std::io::Error::other(message)
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Setup dotenv
-    dotenvy::dotenv().unwrap();
+    // Setup dotenv, the .env file is optional
+    if let Err(err) = dotenvy::dotenv()
+        && !err.not_found()
+    {
+        return Err(startup_error(format!("The .env file can't be read. Error: {}", err)));
+    }
+
+    // Setup config
+    if let Err(errors) = init_config() {
+        for err in &errors {
+            log_error("Config", err.to_string().as_str());
+        }
+        return Err(startup_error(format!("The config has {} problem(s).", errors.len())));
+    }
+    let config: &'static Config = get_config();
 
     // Setup SurrealDB
-    init_db().await;
+    if let Err(err) = init_db().await {
+        return Err(startup_error(format!("Can't set up SurrealDB. Error: {}", err)));
+    }
+
+    // Setup vote receipts
+    if config.election.receipt_secret.is_none() {
+        log_error("Receipt", "RECEIPT_SECRET is not set, vote receipts are disabled.");
+    }
 
     // Setup Static Data
     init_voters_data().await;
     init_votes_count().await;
     init_candidates_data().await;
     init_admin_data().await;
+    init_elections_data().await;
 
     // Setup Redis
-    let redis_url: String = std::env::var("REDIS_URL").unwrap();
-
     let redis_configuration: RedisConfig = RedisConfig {
-        url: Some(redis_url),
+        url: Some(config.redis.url.clone()),
         connection: None,
-        ..Default::default()
+        pool: Some(RedisPoolConfig::new(config.redis.pool_size)),
+    };
+
+    let redis_pool = match redis_configuration.create_pool(Some(RedisRuntime::Tokio1)) {
+        Ok(data) => data,
+        Err(err) => return Err(startup_error(format!("Can't create the Redis pool. Error: {}", err)))
     };
 
-    let redis_pool = redis_configuration
-        .create_pool(Some(RedisRuntime::Tokio1))
-        .unwrap();
+    // Move the reset tokens stored by voter name and of an older schema
+    let _ = migrate_voters_data_redis(&redis_pool, get_voter_ids_by_name().await).await;
+    let _ = migrate_redis_values(&redis_pool).await;
+    let _ = index_voters_data_redis(&redis_pool).await;
+
+    // Setup the live messages across replicas
+    let redis_client: redis::Client = match redis::Client::open(config.redis.url.as_str()) {
+        Ok(data) => data,
+        Err(err) => return Err(startup_error(format!("Can't create the Redis client. Error: {}", err)))
+    };
+    spawn_leader_election(redis_pool.clone());
+    init_live_broadcast(redis_pool.clone(), redis_client).await;
 
     // Setup HTTP Server
-    log_something("Setup", "Starting...");
-    HttpServer::new(move || {
+    if config.server.cors_allowed_origins.is_empty() {
+        log_something("Setup", "No CORS origin is allowed, set CORS_ALLOWED_ORIGINS when the frontend is served from another origin.");
+    }
+
+    log_something("Setup", format!("Starting on {}...", config.server.bind_address).as_str());
+    let mut http_server = HttpServer::new(move || {
         App::new()
             // State
             .app_data(web::Data::new(redis_pool.clone()))
+            .app_data(json_config())
+            .app_data(query_config())
+            .app_data(path_config())
 
             // Middleware
             .wrap(from_fn(middleware))
-            .wrap(Cors::permissive())
+            .wrap(cors())
 
             // General related API
             .service(candidate_get_api)
+            .service(election_get_api)
+            .service(receipt_get_api)
+            .service(healthz_get_api)
+            .service(readyz_get_api)
 
             // Voter related API
             .service(voter_get_api)
@@ -69,11 +122,37 @@ async fn main() -> std::io::Result<()> {
             .service(admin_votes_api)
             .service(admin_votes_simple_api)
             .service(admin_check_api)
+            .service(admin_logout_api)
+            .service(admin_logout_all_api)
+            .service(admin_admins_get_api)
+            .service(admin_admins_post_api)
+            .service(admin_admins_delete_api)
+            .service(admin_lockouts_get_api)
+            .service(admin_lockouts_delete_api)
+            .service(admin_election_state_api)
+            .service(admin_election_schedule_api)
+            .service(admin_candidates_get_api)
+            .service(admin_candidates_post_api)
+            .service(admin_candidates_order_api)
+            .service(admin_candidates_update_api)
+            .service(admin_candidates_delete_api)
+            .service(admin_voters_get_api)
+            .service(admin_voters_post_api)
+            .service(admin_voters_import_api)
+            .service(admin_voters_update_api)
+            .service(admin_voters_delete_api)
 
             // WebSocket live connectio
             .service(live_votes_data)
-    })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+            .service(admin_live_votes_data)
+    });
+
+    if let Some(workers) = config.server.workers {
+        http_server = http_server.workers(workers);
+    }
+
+    http_server
+        .bind(config.server.bind_address)?
+        .run()
+        .await
 }
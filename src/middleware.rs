@@ -1,16 +1,136 @@
+use std::net::IpAddr;
+
+use actix_cors::Cors;
 use actix_web::{
       body::MessageBody,
       dev::{ServiceRequest, ServiceResponse},
+      http::{StatusCode, header::{HeaderName, HeaderValue, X_FORWARDED_FOR}},
       middleware::Next,
-      Error,
+      error::{JsonPayloadError, PathError, QueryPayloadError},
+      web,
+      Error, HttpRequest, ResponseError,
 };
+use deadpool_redis::Pool as RedisPool;
+use surrealdb::Uuid;
+
+use crate::{config::get_config, error::{AppError, REQUEST_ID}, rdb::{LoginLimitScope, clear_login_failures_redis, get_login_lockout_redis, register_login_failure_redis}, util::log_something};
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+// Polled by the reverse proxy and the monitoring, so they'd flood the logs
+const UNLOGGED_PATHS: [&str; 2] = ["/healthz", "/readyz"];
+
+/// Only the configured origins may send credentialed requests, `*` allows any origin.
+pub fn cors() -> Cors {
+      let cors_allowed_origins: &[String] = &get_config().server.cors_allowed_origins;
+      if cors_allowed_origins.iter().any(|origin| origin == "*") {
+            return Cors::permissive();
+      }
+
+      cors_allowed_origins
+            .iter()
+            .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin.as_str()))
+            .allow_any_method()
+            .allow_any_header()
+            .expose_headers([REQUEST_ID_HEADER])
+            .supports_credentials()
+            .max_age(3600)
+}
 
-use crate::util::log_something;
+
+/// A malformed JSON body is answered like any other validation error.
+pub fn json_config() -> web::JsonConfig {
+      web::JsonConfig::default().error_handler(|err: JsonPayloadError, _: &HttpRequest| AppError::Validation(err.to_string()).into())
+}
+
+/// A malformed query string is answered like any other validation error.
+pub fn query_config() -> web::QueryConfig {
+      web::QueryConfig::default().error_handler(|err: QueryPayloadError, _: &HttpRequest| AppError::Validation(err.to_string()).into())
+}
+
+/// A malformed path parameter is answered like any other validation error.
+pub fn path_config() -> web::PathConfig {
+      web::PathConfig::default().error_handler(|err: PathError, _: &HttpRequest| AppError::Validation(err.to_string()).into())
+}
 
 
+/// Logs the request and gives it an ID, the client can send its own through `X-Request-Id`.
 pub async fn middleware(req: ServiceRequest, next: Next<impl MessageBody>) -> Result<ServiceResponse<impl MessageBody>, Error> {
-      log_something("Middleware", format!("Got a Request! Destination: {}", req.uri().to_string()).as_str());
-      next.call(req).await
+      let request_id: String = req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| !value.is_empty() && value.len() <= 64)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+      if !UNLOGGED_PATHS.contains(&req.path()) {
+            log_something("Middleware", format!("Got a Request! Destination: {} ID: {}", req.uri(), request_id).as_str());
+      }
+      let mut response = REQUEST_ID.scope(request_id.clone(), next.call(req)).await?;
+
+      if let Ok(header_value) = HeaderValue::from_str(request_id.as_str()) {
+            response.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), header_value);
+      }
+
+      Ok(response)
 }
 
+/// The peer address, or the nearest untrusted hop of `X-Forwarded-For` when the peer is a trusted proxy.
+/// Entries added before the trusted proxies are sent by the client and can't be relied on.
+fn get_client_ip(req: &ServiceRequest) -> String {
+      let trusted_proxies: &[IpAddr] = &get_config().server.trusted_proxies;
+      let mut client_ip: IpAddr = match req.peer_addr() {
+            Some(address) => address.ip(),
+            None => return String::from("unknown")
+      };
 
+      let forwarded_ips: Vec<Option<IpAddr>> = req.headers()
+            .get_all(X_FORWARDED_FOR)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|data| data.trim().parse::<IpAddr>().ok())
+            .collect();
+
+      let mut forwarded_ips = forwarded_ips.into_iter().rev();
+      while trusted_proxies.contains(&client_ip) {
+            match forwarded_ips.next() {
+                  Some(Some(forwarded_ip)) => client_ip = forwarded_ip,
+                  _ => break
+            }
+      }
+
+      client_ip.to_string()
+}
+
+/// Locks out the client IP after too many failed logins. Attach it to login routes with `wrap`.
+pub async fn login_rate_limit(req: ServiceRequest, next: Next<impl MessageBody + 'static>) -> Result<ServiceResponse<impl MessageBody>, Error> {
+      let redis_pool = match req.app_data::<web::Data<RedisPool>>() {
+            Some(data) => data.clone(),
+            None => {
+                  let err = AppError::Internal(String::from("The redis pool isn't registered as app data."));
+                  return Ok(req.into_response(err.error_response()));
+            }
+      };
+      let client_ip: String = get_client_ip(&req);
+
+      // Reject the request when the IP is locked out
+      match get_login_lockout_redis(&redis_pool, LoginLimitScope::Ip, client_ip.as_str()).await {
+            Ok(Some(remaining_lockout)) => {
+                  return Ok(req.into_response(AppError::TooManyRequests(remaining_lockout).error_response()));
+            },
+            Ok(None) => (),
+            Err(err) => {
+                  return Ok(req.into_response(err.error_response()));
+            }
+      }
+
+      // Count the failure or reset it depending on the login result
+      let response = next.call(req).await?.map_into_boxed_body();
+      if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::NOT_FOUND) {
+            let _ = register_login_failure_redis(&redis_pool, LoginLimitScope::Ip, client_ip.as_str()).await;
+      }
+      else if response.status().is_success() {
+            let _ = clear_login_failures_redis(&redis_pool, LoginLimitScope::Ip, client_ip.as_str()).await;
+      }
+
+      Ok(response)
+}
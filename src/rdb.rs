@@ -1,11 +1,64 @@
-use std::{collections::HashMap};
+use std::{collections::HashMap, future::Future};
 
-use actix_web::HttpResponse;
-use deadpool_redis::{PoolError, Pool as RedisPool, Connection as RedisConnection};
-use redis::{AsyncCommands, RedisError};
-use serde::{Deserialize, Serialize};
+use deadpool_redis::{Pool as RedisPool, Connection as RedisConnection};
+use futures_util::StreamExt;
+use redis::{AsyncCommands, ExistenceCheck, RedisError, SetExpiry, SetOptions, aio::ConnectionLike};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
-use crate::{db::Campus, util::log_error};
+use crate::{config::{LoginLimitConfig, SessionConfig, get_config}, db::Campus, error::{AppError, AppResult}, util::{get_timestamp, log_error, log_something}};
+
+#[cfg(test)]
+mod fake_redis;
+#[cfg(test)]
+mod tests;
+
+// Reset tokens of the voters by their student number
+const VOTER_TOKEN_RESET_KEY: &str = "voter_token_reset";
+// Locked out logins scored by the end of their lockout
+const LOGIN_LOCKOUTS_KEY: &str = "login_lockouts";
+const LEADER_LOCK_KEY: &str = "leader_lock";
+// Pub/sub channels of the live messages
+const LIVE_CHANNEL: &str = "live:public";
+const ADMIN_LIVE_CHANNEL: &str = "live:admin";
+
+/// JSON values stored in Redis, wrapped with the version of their schema as `{"v": 1, "data": ...}`.
+pub trait RedisValueType: Serialize + DeserializeOwned {
+      const VERSION: u32;
+}
+
+#[derive(Serialize)]
+struct RedisValueEnvelope<'a, T> {
+      v: u32,
+      data: &'a T
+}
+
+#[derive(Deserialize)]
+struct RedisStoredValueEnvelope {
+      v: u32,
+      data: serde_json::Value
+}
+
+pub fn encode_redis_value<T: RedisValueType>(value: &T) -> Result<String, serde_json::Error> {
+      serde_json::to_string(&RedisValueEnvelope {
+            v: T::VERSION,
+            data: value
+      })
+}
+
+/// Values stored before they were versioned are read as they are.
+pub fn decode_redis_value<T: RedisValueType>(raw_value: &str) -> Result<T, serde_json::Error> {
+      let value: serde_json::Value = serde_json::from_str(raw_value)?;
+
+      match serde_json::from_value::<RedisStoredValueEnvelope>(value.clone()) {
+            Ok(envelope) if envelope.v == T::VERSION => serde_json::from_value::<T>(envelope.data),
+            Ok(envelope) => Err(serde::de::Error::custom(format!("unsupported version {} of the redis value, expected {}", envelope.v, T::VERSION))),
+            Err(_) => serde_json::from_value::<T>(value),
+      }
+}
+
+fn is_current_redis_value<T: RedisValueType>(raw_value: &str) -> bool {
+      serde_json::from_str::<RedisStoredValueEnvelope>(raw_value).is_ok_and(|envelope| envelope.v == T::VERSION)
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RedisVoterType {
@@ -13,33 +66,114 @@ pub struct RedisVoterType {
       pub campus: Campus
 }
 
-pub async fn get_voters_data_redis(redis_pool: &RedisPool) -> Result<HashMap<String, RedisVoterType>, HttpResponse> {
-      let redis_connection_result: Result<RedisConnection, PoolError>  = redis_pool.get().await;
-      let mut redis_connection: RedisConnection = match redis_connection_result {
-            Ok(connection) => connection,
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RedisAdminSessionType {
+      pub admin_id: String,
+      pub created_at: i64,
+      pub last_seen_at: i64
+}
+
+impl RedisValueType for RedisVoterType {
+      const VERSION: u32 = 1;
+}
+
+impl RedisValueType for RedisAdminSessionType {
+      const VERSION: u32 = 1;
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoginLimitScope {
+      Ip,
+      Voter,
+      Admin
+}
+
+impl LoginLimitScope {
+      pub fn as_str(&self) -> &'static str {
+            match self {
+                  LoginLimitScope::Ip => "ip",
+                  LoginLimitScope::Voter => "voter",
+                  LoginLimitScope::Admin => "admin",
+            }
+      }
+
+      pub fn parse(scope: &str) -> Option<LoginLimitScope> {
+            match scope {
+                  "ip" => Some(LoginLimitScope::Ip),
+                  "voter" => Some(LoginLimitScope::Voter),
+                  "admin" => Some(LoginLimitScope::Admin),
+                  _ => None,
+            }
+      }
+}
+
+/// The WebSocket clients a live message is meant for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveChannel {
+      Public,
+      Admin
+}
+
+impl LiveChannel {
+      fn as_str(&self) -> &'static str {
+            match self {
+                  LiveChannel::Public => LIVE_CHANNEL,
+                  LiveChannel::Admin => ADMIN_LIVE_CHANNEL,
+            }
+      }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RedisLoginLockoutType {
+      pub scope: LoginLimitScope,
+      pub key: String,
+      pub locked_until: i64
+}
+
+// The reset token, session and lockout flows take any connection so the tests can run them on an in-memory fake
+async fn get_redis_connection(redis_pool: &RedisPool, scope_title: &str) -> AppResult<RedisConnection> {
+      match redis_pool.get().await {
+            Ok(connection) => Ok(connection),
             Err(err) => {
-                  log_error("GetTokenRedis", format!("There's an error when trying to get redis pool. Error: {}", err.to_string()).as_str());
-                  return Err(HttpResponse::InternalServerError().finish());
+                  log_error(scope_title, format!("There's an error when trying to get redis pool. Error: {}", err).as_str());
+                  Err(AppError::from(err))
             }
-      };
+      }
+}
 
-      let redis_voter_tokens: Result<HashMap<String, String>, redis::RedisError>  = redis_connection.hgetall("voter_token_reset").await;
+/// Used by the readiness check, which reports the error itself.
+pub async fn ping_redis(redis_pool: &RedisPool) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = redis_pool.get().await?;
+      let _: String = redis::cmd("PING").query_async(&mut redis_connection).await?;
+
+      Ok(())
+}
+
+pub async fn get_voters_data_redis(redis_pool: &RedisPool) -> AppResult<HashMap<String, RedisVoterType>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetTokenRedis").await?;
+      get_voters_data(&mut redis_connection).await
+}
+
+async fn get_voters_data<C: ConnectionLike + Send + Sync>(redis_connection: &mut C) -> AppResult<HashMap<String, RedisVoterType>> {
+      let redis_voter_tokens: Result<HashMap<String, String>, redis::RedisError>  = redis_connection.hgetall(VOTER_TOKEN_RESET_KEY).await;
       let redis_voter_tokens: HashMap<String, String> = match redis_voter_tokens {
             Ok(data) => data,
             Err(err) => {
-                  log_error("GetTokenRedis", format!("There's an error when trying to get redis voter. Error: {}", err.to_string()).as_str());
-                  return Err(HttpResponse::InternalServerError().finish());
+                  log_error("GetTokenRedis", format!("There's an error when trying to get redis voter. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
             }
       };
 
+      // A value that can't be read only loses its own reset token, it's removed by the next startup migration
       let mut redis_voter_tokens_deserialized: HashMap<String, RedisVoterType> = HashMap::new();
       for redis_token in redis_voter_tokens.iter() {
-            let deserialized_voter_data: Result<RedisVoterType, serde_json::Error> = serde_json::from_str::<RedisVoterType>(redis_token.1);
+            let deserialized_voter_data: Result<RedisVoterType, serde_json::Error> = decode_redis_value::<RedisVoterType>(redis_token.1);
             let deserialized_voter_data = match deserialized_voter_data {
                   Ok(data) => data,
                   Err(err) => {
-                        log_error("GetTokenRedis", format!("There's an error when trying to get deserialize redis voter data. Error: {}", err.to_string()).as_str());
-                        return Err(HttpResponse::InternalServerError().finish());
+                        log_error("GetTokenRedis", format!("The reset token of {} can't be read and is skipped. Error: {}", redis_token.0, err).as_str());
+                        continue;
                   }
             };
 
@@ -49,39 +183,602 @@ pub async fn get_voters_data_redis(redis_pool: &RedisPool) -> Result<HashMap<Str
       Ok(redis_voter_tokens_deserialized)
 }
 
-pub async fn set_voters_data_redis(redis_pool: &RedisPool, voter_name: &str, new_voter_token: &str, campus_name: &Campus) -> Result<(), HttpResponse> {
-      let redis_connection_result: Result<deadpool_redis::Connection, PoolError>  = redis_pool.get().await;
-      let mut redis_connection: deadpool_redis::Connection = match redis_connection_result {
-            Ok(connection) => connection,
+fn voter_token_key(voter_token: &str) -> String {
+      format!("voter_token:{}", voter_token)
+}
+
+/// Get the reset token of a voter, `None` if the token has never been reset.
+pub async fn get_voter_reset_token_redis(redis_pool: &RedisPool, voter_id: &str) -> AppResult<Option<String>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetTokenRedis").await?;
+      get_voter_reset_token(&mut redis_connection, voter_id).await
+}
+
+async fn get_voter_reset_token<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, voter_id: &str) -> AppResult<Option<String>> {
+      let redis_voter_data: Result<Option<String>, RedisError> = redis_connection.hget(VOTER_TOKEN_RESET_KEY, voter_id).await;
+      let redis_voter_data: String = match redis_voter_data {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                  return Ok(None);
+            },
             Err(err) => {
-                  log_error("SetTokenRedis", format!("There's an error when trying to get redis pool. Error: {}", err.to_string()).as_str());
-                  return Err(HttpResponse::InternalServerError().finish());
+                  log_error("GetTokenRedis", format!("There's an error when trying to get redis voter. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
             }
       };
 
+      match decode_redis_value::<RedisVoterType>(redis_voter_data.as_str()) {
+            Ok(data) => Ok(Some(data.token)),
+            Err(err) => {
+                  log_error("GetTokenRedis", format!("There's an error when trying to get deserialize redis voter data. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+/// Get the voter of a reset token through the reverse mapping.
+pub async fn get_voter_id_by_reset_token_redis(redis_pool: &RedisPool, voter_token: &str) -> AppResult<Option<String>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetTokenRedis").await?;
+      get_voter_id_by_reset_token(&mut redis_connection, voter_token).await
+}
+
+async fn get_voter_id_by_reset_token<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, voter_token: &str) -> AppResult<Option<String>> {
+      let voter_id: Result<Option<String>, RedisError> = redis_connection.get(voter_token_key(voter_token)).await;
+      match voter_id {
+            Ok(data) => Ok(data),
+            Err(err) => {
+                  log_error("GetTokenRedis", format!("There's an error when trying to get redis voter token. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+pub async fn set_voters_data_redis(redis_pool: &RedisPool, voter_id: &str, new_voter_token: &str, campus_name: &Campus) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "SetTokenRedis").await?;
+      set_voters_data(&mut redis_connection, voter_id, new_voter_token, campus_name).await
+}
 
-      let serialized_data: Result<String, serde_json::Error> = serde_json::to_string(&RedisVoterType {
-            campus: campus_name.clone(),
+async fn set_voters_data<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, voter_id: &str, new_voter_token: &str, campus_name: &Campus) -> AppResult<()> {
+      let previous_voter_token: Option<String> = get_voter_reset_token(redis_connection, voter_id).await?;
+
+      let serialized_data: Result<String, serde_json::Error> = encode_redis_value(&RedisVoterType {
+            campus: *campus_name,
             token: new_voter_token.to_string()
       });
       let serialized_data: String = match serialized_data {
             Ok(data) => data,
             Err(err) => {
-                  log_error("SetTokenRedis", format!("There's an error when trying to serialize redis voter data. Error: {}", err.to_string()).as_str());
-                  return Err(HttpResponse::InternalServerError().finish());
+                  log_error("SetTokenRedis", format!("There's an error when trying to serialize redis voter data. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
             }
       };
 
-      let insert_result: Result<(), RedisError> = redis_connection.hset("voter_token_reset", voter_name.to_string(), serialized_data).await;
+      // Keep the reverse mapping of the token to the voter
+      let mut pipeline = redis::pipe();
+      pipeline.atomic();
+      if let Some(previous_voter_token) = previous_voter_token {
+            pipeline.del(voter_token_key(previous_voter_token.as_str())).ignore();
+      }
+      pipeline
+            .hset(VOTER_TOKEN_RESET_KEY, voter_id, serialized_data).ignore()
+            .set(voter_token_key(new_voter_token), voter_id).ignore();
 
+      let insert_result: Result<(), RedisError> = pipeline.query_async(redis_connection).await;
       match insert_result {
             Ok(_) => (),
             Err(err) => {
-                  log_error("SetTokenRedis", format!("There's an error when trying to reset a voter token to Redis. Error: {}", err.to_string()).as_str());
-                  return Err(HttpResponse::InternalServerError().finish());
+                  log_error("SetTokenRedis", format!("There's an error when trying to reset a voter token to Redis. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      }
+
+
+      Ok(())
+}
+
+/// Create the missing reverse mappings of the reset tokens stored before they existed.
+pub async fn index_voters_data_redis(redis_pool: &RedisPool) -> AppResult<()> {
+      let redis_voter_tokens: HashMap<String, RedisVoterType> = get_voters_data_redis(redis_pool).await?;
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "IndexTokenRedis").await?;
+
+      let mut pipeline = redis::pipe();
+      for (voter_id, redis_voter_data) in redis_voter_tokens.iter() {
+            pipeline.set_nx(voter_token_key(redis_voter_data.token.as_str()), voter_id).ignore();
+      }
+
+      let index_result: Result<(), RedisError> = pipeline.query_async(&mut redis_connection).await;
+      match index_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("IndexTokenRedis", format!("There's an error when trying to index the reset tokens. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+/// Move the reset tokens stored by voter name to the voter ID. Names shared by several voters are
+/// left alone, they can't be told apart and need another reset.
+pub async fn migrate_voters_data_redis(redis_pool: &RedisPool, voter_ids_by_name: HashMap<String, Vec<String>>) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "MigrateTokenRedis").await?;
+
+      let redis_voter_names: Result<Vec<String>, RedisError> = redis_connection.hkeys(VOTER_TOKEN_RESET_KEY).await;
+      let redis_voter_names: Vec<String> = match redis_voter_names {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("MigrateTokenRedis", format!("There's an error when trying to get redis voter. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      for redis_voter_name in redis_voter_names {
+            let voter_id: &String = match voter_ids_by_name.get(&redis_voter_name).map(|voter_ids| voter_ids.as_slice()) {
+                  Some([voter_id]) if *voter_id != redis_voter_name => voter_id,
+                  Some([_, _, ..]) => {
+                        log_error("MigrateTokenRedis", format!("The reset token of {} is shared by several voters, reset them again.", redis_voter_name).as_str());
+                        continue;
+                  },
+                  _ => continue,
+            };
+
+            let migrate_result: Result<(), RedisError> = redis::Script::new(r"
+                  local data = redis.call('HGET', KEYS[1], ARGV[1])
+                  if data and redis.call('HSETNX', KEYS[1], ARGV[2], data) == 1 then
+                        redis.call('HDEL', KEYS[1], ARGV[1])
+                  end
+            ")
+                  .key(VOTER_TOKEN_RESET_KEY)
+                  .arg(redis_voter_name.as_str())
+                  .arg(voter_id.as_str())
+                  .invoke_async(&mut redis_connection)
+                  .await;
+
+            match migrate_result {
+                  Ok(_) => {
+                        log_something("MigrateTokenRedis", format!("Moved the reset token of {} to {}", redis_voter_name, voter_id).as_str());
+                  },
+                  Err(err) => {
+                        log_error("MigrateTokenRedis", format!("There's an error when trying to move the reset token of {}. Error: {}", redis_voter_name, err).as_str());
+                        return Err(AppError::from(err));
+                  }
+            }
+      }
+
+      Ok(())
+}
+
+
+/// Rewrite the reset tokens stored before their values were versioned and remove the ones that can't be read.
+/// The admin sessions aren't migrated, they're rewritten on every request and expire within the idle timeout.
+pub async fn migrate_redis_values(redis_pool: &RedisPool) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "MigrateValuesRedis").await?;
+      migrate_values(&mut redis_connection).await
+}
+
+async fn migrate_values<C: ConnectionLike + Send + Sync>(redis_connection: &mut C) -> AppResult<()> {
+      let redis_voter_tokens: Result<HashMap<String, String>, RedisError> = redis_connection.hgetall(VOTER_TOKEN_RESET_KEY).await;
+      let redis_voter_tokens: HashMap<String, String> = match redis_voter_tokens {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("MigrateValuesRedis", format!("There's an error when trying to get redis voter. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let mut pipeline = redis::pipe();
+      let mut migrated_count: usize = 0;
+      let mut removed_count: usize = 0;
+      for (voter_id, redis_voter_data) in redis_voter_tokens.iter() {
+            if is_current_redis_value::<RedisVoterType>(redis_voter_data) {
+                  continue;
+            }
+
+            let encoded_data: Result<String, serde_json::Error> = decode_redis_value::<RedisVoterType>(redis_voter_data)
+                  .and_then(|data| encode_redis_value(&data));
+            match encoded_data {
+                  Ok(data) => {
+                        pipeline.hset(VOTER_TOKEN_RESET_KEY, voter_id, data).ignore();
+                        migrated_count += 1;
+                  },
+                  Err(err) => {
+                        log_error("MigrateValuesRedis", format!("The reset token of {} can't be read, it's removed and the voter needs another reset. Error: {}", voter_id, err).as_str());
+                        pipeline.hdel(VOTER_TOKEN_RESET_KEY, voter_id).ignore();
+                        removed_count += 1;
+                  }
+            }
+      }
+
+      if migrated_count == 0 && removed_count == 0 {
+            return Ok(());
+      }
+
+      let migrate_result: Result<(), RedisError> = pipeline.query_async(redis_connection).await;
+      match migrate_result {
+            Ok(_) => {
+                  log_something("MigrateValuesRedis", format!("Migrated {} reset tokens to version {} and removed {} unreadable ones", migrated_count, RedisVoterType::VERSION, removed_count).as_str());
+                  Ok(())
+            },
+            Err(err) => {
+                  log_error("MigrateValuesRedis", format!("There's an error when trying to migrate the reset tokens. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+
+fn admin_session_key(admin_session_token: &str) -> String {
+      format!("admin_session:{}", admin_session_token)
+}
+
+fn admin_sessions_key(admin_id: &str) -> String {
+      format!("admin_sessions:{}", admin_id)
+}
+
+pub async fn create_admin_session_redis(redis_pool: &RedisPool, admin_id: &str, admin_session_token: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "CreateAdminSessionRedis").await?;
+      create_admin_session(&mut redis_connection, &get_config().session, admin_id, admin_session_token).await
+}
+
+async fn create_admin_session<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, session_config: &SessionConfig, admin_id: &str, admin_session_token: &str) -> AppResult<()> {
+      let current_timestamp: i64 = get_timestamp();
+      let serialized_data: String = match encode_redis_value(&RedisAdminSessionType {
+            admin_id: admin_id.to_string(),
+            created_at: current_timestamp,
+            last_seen_at: current_timestamp
+      }) {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("CreateAdminSessionRedis", format!("There's an error when trying to serialize admin session. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
             }
+      };
+
+      let insert_result: Result<(), RedisError> = redis::pipe()
+            .set_ex(admin_session_key(admin_session_token), serialized_data, session_config.idle_timeout as u64).ignore()
+            .sadd(admin_sessions_key(admin_id), admin_session_token).ignore()
+            .expire(admin_sessions_key(admin_id), session_config.absolute_timeout).ignore()
+            .query_async(redis_connection)
+            .await;
+
+      match insert_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("CreateAdminSessionRedis", format!("There's an error when trying to create admin session. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+/// Get the admin session and refresh its idle expiry. Expired sessions are removed and return `None`.
+pub async fn get_admin_session_redis(redis_pool: &RedisPool, admin_session_token: &str) -> AppResult<Option<RedisAdminSessionType>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetAdminSessionRedis").await?;
+      get_admin_session(&mut redis_connection, &get_config().session, admin_session_token).await
+}
+
+async fn get_admin_session<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, session_config: &SessionConfig, admin_session_token: &str) -> AppResult<Option<RedisAdminSessionType>> {
+      let session_data: Result<Option<String>, RedisError> = redis_connection.get(admin_session_key(admin_session_token)).await;
+      let session_data: String = match session_data {
+            Ok(Some(data)) => data,
+            Ok(None) => {
+                  return Ok(None);
+            },
+            Err(err) => {
+                  log_error("GetAdminSessionRedis", format!("There's an error when trying to get admin session. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let mut session_data: RedisAdminSessionType = match decode_redis_value::<RedisAdminSessionType>(session_data.as_str()) {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("GetAdminSessionRedis", format!("There's an error when trying to deserialize admin session. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      // Check the absolute expiry
+      let current_timestamp: i64 = get_timestamp();
+      let remaining_lifetime: i64 = session_data.created_at + session_config.absolute_timeout - current_timestamp;
+      if remaining_lifetime <= 0 {
+            remove_admin_session(redis_connection, admin_session_token).await?;
+            return Ok(None);
       }
 
+      // Refresh the idle expiry
+      session_data.last_seen_at = current_timestamp;
+      let serialized_data: String = match encode_redis_value(&session_data) {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("GetAdminSessionRedis", format!("There's an error when trying to serialize admin session. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      // XX so a session removed by a logout in the meantime isn't written back
+      let refresh_options: SetOptions = SetOptions::default()
+            .conditional_set(ExistenceCheck::XX)
+            .with_expiration(SetExpiry::EX(session_config.idle_timeout.min(remaining_lifetime) as u64));
+      let update_result: Result<Option<String>, RedisError> = redis_connection
+            .set_options(admin_session_key(admin_session_token), serialized_data, refresh_options)
+            .await;
+      match update_result {
+            Ok(Some(_)) => Ok(Some(session_data)),
+            Ok(None) => Ok(None),
+            Err(err) => {
+                  log_error("GetAdminSessionRedis", format!("There's an error when trying to refresh admin session. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+pub async fn remove_admin_session_redis(redis_pool: &RedisPool, admin_session_token: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "RemoveAdminSessionRedis").await?;
+      remove_admin_session(&mut redis_connection, admin_session_token).await
+}
+
+async fn remove_admin_session<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, admin_session_token: &str) -> AppResult<()> {
+      let session_data: Result<Option<String>, RedisError> = redis_connection.get(admin_session_key(admin_session_token)).await;
+      let session_data: Option<RedisAdminSessionType> = match session_data {
+            Ok(data) => data.and_then(|data| decode_redis_value::<RedisAdminSessionType>(data.as_str()).ok()),
+            Err(err) => {
+                  log_error("RemoveAdminSessionRedis", format!("There's an error when trying to get admin session. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let mut pipeline = redis::pipe();
+      pipeline.del(admin_session_key(admin_session_token)).ignore();
+      if let Some(session_data) = session_data {
+            pipeline.srem(admin_sessions_key(session_data.admin_id.as_str()), admin_session_token).ignore();
+      }
+
+      let remove_result: Result<(), RedisError> = pipeline.query_async(redis_connection).await;
+      match remove_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("RemoveAdminSessionRedis", format!("There's an error when trying to remove admin session. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+pub async fn remove_all_admin_sessions_redis(redis_pool: &RedisPool, admin_id: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "RemoveAdminSessionRedis").await?;
+      remove_all_admin_sessions(&mut redis_connection, admin_id).await
+}
+
+async fn remove_all_admin_sessions<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, admin_id: &str) -> AppResult<()> {
+      let admin_session_tokens: Result<Vec<String>, RedisError> = redis_connection.smembers(admin_sessions_key(admin_id)).await;
+      let admin_session_tokens: Vec<String> = match admin_session_tokens {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("RemoveAdminSessionRedis", format!("There's an error when trying to get admin sessions. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let mut pipeline = redis::pipe();
+      for admin_session_token in admin_session_tokens.iter() {
+            pipeline.del(admin_session_key(admin_session_token)).ignore();
+      }
+      pipeline.del(admin_sessions_key(admin_id)).ignore();
+
+      let remove_result: Result<(), RedisError> = pipeline.query_async(redis_connection).await;
+      match remove_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("RemoveAdminSessionRedis", format!("There's an error when trying to remove admin sessions. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+
+fn login_lockout_member(scope: LoginLimitScope, key: &str) -> String {
+      format!("{}:{}", scope.as_str(), key)
+}
+
+fn login_failures_key(scope: LoginLimitScope, key: &str) -> String {
+      format!("login_failures:{}", login_lockout_member(scope, key))
+}
+
+/// Get the remaining lockout in seconds, `None` if the login isn't locked.
+pub async fn get_login_lockout_redis(redis_pool: &RedisPool, scope: LoginLimitScope, key: &str) -> AppResult<Option<i64>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetLoginLockoutRedis").await?;
+      get_login_lockout(&mut redis_connection, scope, key).await
+}
+
+async fn get_login_lockout<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, scope: LoginLimitScope, key: &str) -> AppResult<Option<i64>> {
+      let locked_until: Result<Option<f64>, RedisError> = redis_connection.zscore(LOGIN_LOCKOUTS_KEY, login_lockout_member(scope, key)).await;
+      let locked_until: Option<f64> = match locked_until {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("GetLoginLockoutRedis", format!("There's an error when trying to get login lockout. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let remaining_lockout: Option<i64> = locked_until
+            .map(|locked_until| locked_until as i64 - get_timestamp())
+            .filter(|remaining_lockout| *remaining_lockout > 0);
+
+      Ok(remaining_lockout)
+}
+
+pub async fn register_login_failure_redis(redis_pool: &RedisPool, scope: LoginLimitScope, key: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "RegisterLoginFailureRedis").await?;
+      register_login_failure(&mut redis_connection, &get_config().login, scope, key).await
+}
+
+async fn register_login_failure<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, login_config: &LoginLimitConfig, scope: LoginLimitScope, key: &str) -> AppResult<()> {
+      let failures: Result<(i64, ()), RedisError> = redis::pipe()
+            .incr(login_failures_key(scope, key), 1)
+            .expire(login_failures_key(scope, key), login_config.failure_window)
+            .query_async(redis_connection)
+            .await;
+      let failures: i64 = match failures {
+            Ok(data) => data.0,
+            Err(err) => {
+                  log_error("RegisterLoginFailureRedis", format!("There's an error when trying to count login failure. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      if failures < login_config.attempt_limit {
+            return Ok(());
+      }
+
+      // Double the lockout for every failure past the limit
+      let exponent: u32 = (failures - login_config.attempt_limit).min(32) as u32;
+      let lockout_duration: i64 = login_config.lockout_base.saturating_mul(2_i64.saturating_pow(exponent)).min(login_config.lockout_max);
+      let current_timestamp: i64 = get_timestamp();
+
+      let lockout_result: Result<(), RedisError> = redis::pipe()
+            .zrembyscore(LOGIN_LOCKOUTS_KEY, "-inf", current_timestamp).ignore()
+            .zadd(LOGIN_LOCKOUTS_KEY, login_lockout_member(scope, key), current_timestamp + lockout_duration).ignore()
+            .query_async(redis_connection)
+            .await;
+
+      match lockout_result {
+            Ok(_) => {
+                  log_something("LoginLimit", format!("Locked out {} {} for {} seconds.", scope.as_str(), key, lockout_duration).as_str());
+                  Ok(())
+            },
+            Err(err) => {
+                  log_error("RegisterLoginFailureRedis", format!("There's an error when trying to lock out a login. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+pub async fn clear_login_failures_redis(redis_pool: &RedisPool, scope: LoginLimitScope, key: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "ClearLoginFailuresRedis").await?;
+      clear_login_failures(&mut redis_connection, scope, key).await
+}
+
+async fn clear_login_failures<C: ConnectionLike + Send + Sync>(redis_connection: &mut C, scope: LoginLimitScope, key: &str) -> AppResult<()> {
+      let clear_result: Result<(), RedisError> = redis::pipe()
+            .del(login_failures_key(scope, key)).ignore()
+            .zrem(LOGIN_LOCKOUTS_KEY, login_lockout_member(scope, key)).ignore()
+            .query_async(redis_connection)
+            .await;
+
+      match clear_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("ClearLoginFailuresRedis", format!("There's an error when trying to clear login failures. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+pub async fn get_all_login_lockouts_redis(redis_pool: &RedisPool) -> AppResult<Vec<RedisLoginLockoutType>> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "GetLoginLockoutsRedis").await?;
+      get_all_login_lockouts(&mut redis_connection).await
+}
+
+async fn get_all_login_lockouts<C: ConnectionLike + Send + Sync>(redis_connection: &mut C) -> AppResult<Vec<RedisLoginLockoutType>> {
+      let lockouts: Result<Vec<(String, f64)>, RedisError> = redis_connection
+            .zrangebyscore_withscores(LOGIN_LOCKOUTS_KEY, get_timestamp(), "+inf")
+            .await;
+      let lockouts: Vec<(String, f64)> = match lockouts {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("GetLoginLockoutsRedis", format!("There's an error when trying to get login lockouts. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      let lockouts: Vec<RedisLoginLockoutType> = lockouts
+            .into_iter()
+            .filter_map(|(member, locked_until)| {
+                  let (scope, key) = member.split_once(':')?;
+                  Some(RedisLoginLockoutType {
+                        scope: LoginLimitScope::parse(scope)?,
+                        key: key.to_string(),
+                        locked_until: locked_until as i64
+                  })
+            })
+            .collect();
+
+      Ok(lockouts)
+}
+
+
+/// Acquire the leader lock or renew it when this instance already holds it.
+pub async fn renew_leader_lock_redis(redis_pool: &RedisPool, instance_id: &str) -> bool {
+      let mut redis_connection: RedisConnection = match redis_pool.get().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                  log_error("RenewLeaderLockRedis", format!("There's an error when trying to get redis pool. Error: {}", err).as_str());
+                  return false;
+            }
+      };
+
+      let renew_script = redis::Script::new(r"
+            if redis.call('GET', KEYS[1]) == ARGV[1] then
+                  return redis.call('EXPIRE', KEYS[1], ARGV[2])
+            end
+            if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'EX', ARGV[2]) then
+                  return 1
+            end
+            return 0
+      ");
+
+      let renew_result: Result<i64, RedisError> = renew_script
+            .key(LEADER_LOCK_KEY)
+            .arg(instance_id)
+            .arg(get_config().redis.leader_lock_timeout)
+            .invoke_async(&mut redis_connection)
+            .await;
+
+      match renew_result {
+            Ok(data) => data == 1,
+            Err(err) => {
+                  log_error("RenewLeaderLockRedis", format!("There's an error when trying to renew the leader lock. Error: {}", err).as_str());
+                  false
+            }
+      }
+}
+
+pub async fn publish_live_message_redis(redis_pool: &RedisPool, channel: LiveChannel, message: &str) -> AppResult<()> {
+      let mut redis_connection: RedisConnection = get_redis_connection(redis_pool, "LivePublisher").await?;
+
+      let publish_result: Result<(), RedisError> = redis_connection.publish(channel.as_str(), message).await;
+      match publish_result {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("LivePublisher", format!("There's an error when trying to publish a live message. Error: {}", err).as_str());
+                  Err(AppError::from(err))
+            }
+      }
+}
+
+/// Hand every live message to `on_message` until the subscription is dropped.
+pub async fn subscribe_live_messages_redis<F, Fut>(redis_client: &redis::Client, mut on_message: F) -> AppResult<()>
+where
+      F: FnMut(LiveChannel, String) -> Fut,
+      Fut: Future<Output = ()>
+{
+      let mut pubsub = redis_client.get_async_pubsub().await?;
+      pubsub.subscribe(&[LIVE_CHANNEL, ADMIN_LIVE_CHANNEL]).await?;
+
+      log_something("LiveSubscriber", "Subscribed to the live messages.");
+
+      let mut msg_stream = pubsub.on_message();
+      while let Some(msg) = msg_stream.next().await {
+            let message: String = match msg.get_payload::<String>() {
+                  Ok(data) => data,
+                  Err(err) => {
+                        log_error("LiveSubscriber", format!("There's an error when trying to read a live message. Error: {}", err).as_str());
+                        continue;
+                  }
+            };
+
+            let channel: LiveChannel = match msg.get_channel_name() {
+                  ADMIN_LIVE_CHANNEL => LiveChannel::Admin,
+                  _ => LiveChannel::Public,
+            };
+            on_message(channel, message).await;
+      }
 
       Ok(())
 }
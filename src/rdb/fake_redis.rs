@@ -0,0 +1,307 @@
+use std::collections::{HashMap, HashSet};
+
+use redis::{Arg, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult, Value, aio::ConnectionLike};
+
+enum FakeValueType {
+      String(String),
+      Hash(HashMap<String, String>),
+      Set(HashSet<String>),
+      SortedSet(HashMap<String, f64>)
+}
+
+/// An in-memory stand-in of the Redis commands used by the reset token, session and lockout flows.
+/// The expiries are only recorded, nothing expires on its own.
+#[derive(Default)]
+pub struct FakeRedisConnection {
+      values: HashMap<String, FakeValueType>,
+      expiries: HashMap<String, i64>
+}
+
+fn response_error(message: &str) -> RedisError {
+      RedisError::from((ErrorKind::ResponseError, "fake redis", message.to_string()))
+}
+
+fn wrong_type_error() -> RedisError {
+      response_error("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+fn bulk_string(data: impl Into<String>) -> Value {
+      Value::BulkString(data.into().into_bytes())
+}
+
+fn parse_number<T: std::str::FromStr>(data: &str) -> RedisResult<T> {
+      data.parse::<T>().map_err(|_| response_error("value is not a number"))
+}
+
+fn parse_score_bound(data: &str) -> RedisResult<f64> {
+      match data {
+            "-inf" => Ok(f64::NEG_INFINITY),
+            "+inf" | "inf" => Ok(f64::INFINITY),
+            _ => parse_number::<f64>(data)
+      }
+}
+
+impl FakeRedisConnection {
+      pub fn new() -> FakeRedisConnection {
+            FakeRedisConnection::default()
+      }
+
+      pub fn set_string(&mut self, key: &str, value: &str) {
+            self.values.insert(key.to_string(), FakeValueType::String(value.to_string()));
+      }
+
+      pub fn get_hash_field(&self, key: &str, field: &str) -> Option<String> {
+            match self.values.get(key) {
+                  Some(FakeValueType::Hash(data)) => data.get(field).cloned(),
+                  _ => None
+            }
+      }
+
+      pub fn set_hash_field(&mut self, key: &str, field: &str, value: &str) {
+            if let FakeValueType::Hash(data) = self.values.entry(key.to_string()).or_insert_with(|| FakeValueType::Hash(HashMap::new())) {
+                  data.insert(field.to_string(), value.to_string());
+            }
+      }
+
+      pub fn contains_key(&self, key: &str) -> bool {
+            self.values.contains_key(key)
+      }
+
+      /// The expiry in seconds last set on the key.
+      pub fn get_ttl(&self, key: &str) -> Option<i64> {
+            self.expiries.get(key).copied()
+      }
+
+      fn remove(&mut self, key: &str) -> bool {
+            self.expiries.remove(key);
+            self.values.remove(key).is_some()
+      }
+
+      fn hash_mut(&mut self, key: &str) -> RedisResult<&mut HashMap<String, String>> {
+            match self.values.entry(key.to_string()).or_insert_with(|| FakeValueType::Hash(HashMap::new())) {
+                  FakeValueType::Hash(data) => Ok(data),
+                  _ => Err(wrong_type_error())
+            }
+      }
+
+      fn set_mut(&mut self, key: &str) -> RedisResult<&mut HashSet<String>> {
+            match self.values.entry(key.to_string()).or_insert_with(|| FakeValueType::Set(HashSet::new())) {
+                  FakeValueType::Set(data) => Ok(data),
+                  _ => Err(wrong_type_error())
+            }
+      }
+
+      fn sorted_set_mut(&mut self, key: &str) -> RedisResult<&mut HashMap<String, f64>> {
+            match self.values.entry(key.to_string()).or_insert_with(|| FakeValueType::SortedSet(HashMap::new())) {
+                  FakeValueType::SortedSet(data) => Ok(data),
+                  _ => Err(wrong_type_error())
+            }
+      }
+
+      /// Drop the collections emptied by a command, as Redis does.
+      fn remove_if_empty(&mut self, key: &str) {
+            let is_empty: bool = match self.values.get(key) {
+                  Some(FakeValueType::Hash(data)) => data.is_empty(),
+                  Some(FakeValueType::Set(data)) => data.is_empty(),
+                  Some(FakeValueType::SortedSet(data)) => data.is_empty(),
+                  _ => false
+            };
+
+            if is_empty {
+                  self.remove(key);
+            }
+      }
+
+      fn set(&mut self, args: &[String]) -> RedisResult<Value> {
+            let (key, value) = (&args[0], &args[1]);
+            let mut only_if_missing: bool = false;
+            let mut only_if_present: bool = false;
+            let mut expiry: Option<i64> = None;
+
+            let mut options = args[2..].iter();
+            while let Some(option) = options.next() {
+                  match option.to_uppercase().as_str() {
+                        "NX" => only_if_missing = true,
+                        "XX" => only_if_present = true,
+                        "EX" => expiry = Some(parse_number::<i64>(options.next().ok_or_else(|| response_error("syntax error"))?)?),
+                        _ => return Err(response_error("syntax error"))
+                  }
+            }
+
+            let exists: bool = self.values.contains_key(key);
+            if (only_if_missing && exists) || (only_if_present && !exists) {
+                  return Ok(Value::Nil);
+            }
+
+            self.remove(key);
+            self.set_string(key, value);
+            if let Some(expiry) = expiry {
+                  self.expiries.insert(key.clone(), expiry);
+            }
+
+            Ok(Value::Okay)
+      }
+
+      fn execute(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+            let args: Vec<String> = cmd.args_iter()
+                  .filter_map(|arg| match arg {
+                        Arg::Simple(data) => Some(String::from_utf8_lossy(data).to_string()),
+                        Arg::Cursor => None
+                  })
+                  .collect();
+            let Some((name, args)) = args.split_first() else {
+                  return Err(response_error("empty command"));
+            };
+
+            match name.to_uppercase().as_str() {
+                  "PING" => Ok(Value::SimpleString(String::from("PONG"))),
+                  "GET" => match self.values.get(&args[0]) {
+                        Some(FakeValueType::String(data)) => Ok(bulk_string(data.clone())),
+                        Some(_) => Err(wrong_type_error()),
+                        None => Ok(Value::Nil)
+                  },
+                  "SET" => self.set(args),
+                  "SETEX" => self.set(&[args[0].clone(), args[2].clone(), String::from("EX"), args[1].clone()]),
+                  "SETNX" => match self.set(&[args[0].clone(), args[1].clone(), String::from("NX")])? {
+                        Value::Nil => Ok(Value::Int(0)),
+                        _ => Ok(Value::Int(1))
+                  },
+                  "DEL" => Ok(Value::Int(args.iter().filter(|key| self.remove(key)).count() as i64)),
+                  "EXPIRE" => {
+                        if !self.values.contains_key(&args[0]) {
+                              return Ok(Value::Int(0));
+                        }
+                        self.expiries.insert(args[0].clone(), parse_number::<i64>(&args[1])?);
+                        Ok(Value::Int(1))
+                  },
+                  "INCRBY" => {
+                        let current_value: i64 = match self.values.get(&args[0]) {
+                              Some(FakeValueType::String(data)) => parse_number::<i64>(data)?,
+                              Some(_) => return Err(wrong_type_error()),
+                              None => 0
+                        };
+                        let new_value: i64 = current_value + parse_number::<i64>(&args[1])?;
+                        self.values.insert(args[0].clone(), FakeValueType::String(new_value.to_string()));
+                        Ok(Value::Int(new_value))
+                  },
+                  "HGET" => Ok(self.hash_mut(&args[0])?.get(&args[1]).cloned().map(bulk_string).unwrap_or(Value::Nil))
+                        .inspect(|_| self.remove_if_empty(&args[0])),
+                  "HSET" => {
+                        let hash: &mut HashMap<String, String> = self.hash_mut(&args[0])?;
+                        let added: usize = args[1..]
+                              .chunks(2)
+                              .filter(|pair| hash.insert(pair[0].clone(), pair[1].clone()).is_none())
+                              .count();
+                        Ok(Value::Int(added as i64))
+                  },
+                  "HDEL" => {
+                        let hash: &mut HashMap<String, String> = self.hash_mut(&args[0])?;
+                        let removed: usize = args[1..].iter().filter(|field| hash.remove(*field).is_some()).count();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Int(removed as i64))
+                  },
+                  "HGETALL" => {
+                        let values: Vec<Value> = self.hash_mut(&args[0])?
+                              .iter()
+                              .flat_map(|(field, value)| [bulk_string(field.clone()), bulk_string(value.clone())])
+                              .collect();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Array(values))
+                  },
+                  "HKEYS" => {
+                        let values: Vec<Value> = self.hash_mut(&args[0])?.keys().cloned().map(bulk_string).collect();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Array(values))
+                  },
+                  "SADD" => {
+                        let set: &mut HashSet<String> = self.set_mut(&args[0])?;
+                        Ok(Value::Int(args[1..].iter().filter(|member| set.insert((*member).clone())).count() as i64))
+                  },
+                  "SREM" => {
+                        let set: &mut HashSet<String> = self.set_mut(&args[0])?;
+                        let removed: usize = args[1..].iter().filter(|member| set.remove(*member)).count();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Int(removed as i64))
+                  },
+                  "SMEMBERS" => {
+                        let values: Vec<Value> = self.set_mut(&args[0])?.iter().cloned().map(bulk_string).collect();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Array(values))
+                  },
+                  "ZADD" => {
+                        let sorted_set: &mut HashMap<String, f64> = self.sorted_set_mut(&args[0])?;
+                        let mut added: i64 = 0;
+                        for pair in args[1..].chunks(2) {
+                              if sorted_set.insert(pair[1].clone(), parse_number::<f64>(&pair[0])?).is_none() {
+                                    added += 1;
+                              }
+                        }
+                        Ok(Value::Int(added))
+                  },
+                  "ZSCORE" => {
+                        let score: Option<f64> = self.sorted_set_mut(&args[0])?.get(&args[1]).copied();
+                        self.remove_if_empty(&args[0]);
+                        Ok(score.map(|score| bulk_string(score.to_string())).unwrap_or(Value::Nil))
+                  },
+                  "ZREM" => {
+                        let sorted_set: &mut HashMap<String, f64> = self.sorted_set_mut(&args[0])?;
+                        let removed: usize = args[1..].iter().filter(|member| sorted_set.remove(*member).is_some()).count();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Int(removed as i64))
+                  },
+                  "ZREMRANGEBYSCORE" => {
+                        let (min, max): (f64, f64) = (parse_score_bound(&args[1])?, parse_score_bound(&args[2])?);
+                        let sorted_set: &mut HashMap<String, f64> = self.sorted_set_mut(&args[0])?;
+                        let count_before: usize = sorted_set.len();
+                        sorted_set.retain(|_, score| *score < min || *score > max);
+                        let removed: usize = count_before - sorted_set.len();
+                        self.remove_if_empty(&args[0]);
+                        Ok(Value::Int(removed as i64))
+                  },
+                  "ZRANGEBYSCORE" => {
+                        let (min, max): (f64, f64) = (parse_score_bound(&args[1])?, parse_score_bound(&args[2])?);
+                        let with_scores: bool = args.get(3).is_some_and(|option| option.eq_ignore_ascii_case("WITHSCORES"));
+                        let mut members: Vec<(String, f64)> = self.sorted_set_mut(&args[0])?
+                              .iter()
+                              .filter(|(_, score)| **score >= min && **score <= max)
+                              .map(|(member, score)| (member.clone(), *score))
+                              .collect();
+                        self.remove_if_empty(&args[0]);
+                        members.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+                        let values: Vec<Value> = members
+                              .into_iter()
+                              .flat_map(|(member, score)| match with_scores {
+                                    true => vec![bulk_string(member), bulk_string(score.to_string())],
+                                    false => vec![bulk_string(member)]
+                              })
+                              .collect();
+                        Ok(Value::Array(values))
+                  },
+                  command => Err(response_error(format!("unsupported command {}", command).as_str()))
+            }
+      }
+}
+
+impl ConnectionLike for FakeRedisConnection {
+      fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+            let result: RedisResult<Value> = self.execute(cmd);
+            Box::pin(async move { result })
+      }
+
+      fn req_packed_commands<'a>(&'a mut self, pipeline: &'a Pipeline, _offset: usize, _count: usize) -> RedisFuture<'a, Vec<Value>> {
+            // A transaction answers with the results of its commands in the single reply of EXEC
+            let result: RedisResult<Vec<Value>> = pipeline.cmd_iter()
+                  .map(|cmd| self.execute(cmd))
+                  .collect::<RedisResult<Vec<Value>>>()
+                  .map(|values| match pipeline.is_transaction() {
+                        true => vec![Value::Array(values)],
+                        false => values
+                  });
+            Box::pin(async move { result })
+      }
+
+      fn get_db(&self) -> i64 {
+            0
+      }
+}
@@ -0,0 +1,232 @@
+use super::*;
+use super::fake_redis::FakeRedisConnection;
+
+const SESSION_CONFIG: SessionConfig = SessionConfig {
+      idle_timeout: 60 * 60,
+      absolute_timeout: 12 * 60 * 60
+};
+
+const LOGIN_CONFIG: LoginLimitConfig = LoginLimitConfig {
+      attempt_limit: 5,
+      lockout_base: 30,
+      lockout_max: 60 * 60,
+      failure_window: 24 * 60 * 60
+};
+
+fn legacy_voter_value(token: &str) -> String {
+      format!(r#"{{"token":"{}","campus":"MM"}}"#, token)
+}
+
+fn seed_admin_session(redis_connection: &mut FakeRedisConnection, admin_session_token: &str, session_data: &RedisAdminSessionType) {
+      redis_connection.set_string(admin_session_key(admin_session_token).as_str(), encode_redis_value(session_data).unwrap().as_str());
+}
+
+#[test]
+fn encode_wraps_the_value_with_its_version() {
+      let encoded_value: String = encode_redis_value(&RedisVoterType {
+            token: String::from("ABC123"),
+            campus: Campus::PD
+      }).unwrap();
+
+      assert_eq!(encoded_value, r#"{"v":1,"data":{"token":"ABC123","campus":"PD"}}"#);
+      assert!(is_current_redis_value::<RedisVoterType>(encoded_value.as_str()));
+}
+
+#[test]
+fn decode_reads_the_current_version() {
+      let session_data: RedisAdminSessionType = decode_redis_value::<RedisAdminSessionType>(r#"{"v":1,"data":{"admin_id":"admin:1","created_at":10,"last_seen_at":20}}"#).unwrap();
+
+      assert_eq!(session_data.admin_id, "admin:1");
+      assert_eq!(session_data.created_at, 10);
+      assert_eq!(session_data.last_seen_at, 20);
+}
+
+#[test]
+fn decode_reads_the_legacy_unversioned_value() {
+      let legacy_value: String = legacy_voter_value("ABC123");
+      let voter_data: RedisVoterType = decode_redis_value::<RedisVoterType>(legacy_value.as_str()).unwrap();
+
+      assert_eq!(voter_data.token, "ABC123");
+      assert_eq!(voter_data.campus, Campus::MM);
+      assert!(!is_current_redis_value::<RedisVoterType>(legacy_value.as_str()));
+}
+
+#[test]
+fn decode_rejects_an_unsupported_version() {
+      let raw_value: &str = r#"{"v":2,"data":{"token":"ABC123","campus":"MM"}}"#;
+      let err: serde_json::Error = decode_redis_value::<RedisVoterType>(raw_value).unwrap_err();
+
+      assert!(err.to_string().contains("unsupported version 2"));
+      assert!(!is_current_redis_value::<RedisVoterType>(raw_value));
+}
+
+#[test]
+fn decode_rejects_an_invalid_value() {
+      assert!(decode_redis_value::<RedisVoterType>("not json").is_err());
+      assert!(decode_redis_value::<RedisVoterType>(r#"{"token":"ABC123"}"#).is_err());
+}
+
+#[tokio::test]
+async fn reset_token_replaces_the_previous_reverse_mapping() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+
+      set_voters_data(&mut redis_connection, "voter:1", "FIRST1", &Campus::MM).await.unwrap();
+      set_voters_data(&mut redis_connection, "voter:1", "SECOND", &Campus::MM).await.unwrap();
+
+      assert_eq!(get_voter_reset_token(&mut redis_connection, "voter:1").await.unwrap().as_deref(), Some("SECOND"));
+      assert_eq!(get_voter_id_by_reset_token(&mut redis_connection, "SECOND").await.unwrap().as_deref(), Some("voter:1"));
+      assert_eq!(get_voter_id_by_reset_token(&mut redis_connection, "FIRST1").await.unwrap(), None);
+      assert_eq!(get_voter_reset_token(&mut redis_connection, "voter:2").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn unreadable_reset_token_is_skipped() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      set_voters_data(&mut redis_connection, "voter:1", "ABC123", &Campus::PD).await.unwrap();
+      redis_connection.set_hash_field(VOTER_TOKEN_RESET_KEY, "voter:2", "not json");
+
+      let voters_data: HashMap<String, RedisVoterType> = get_voters_data(&mut redis_connection).await.unwrap();
+
+      assert_eq!(voters_data.len(), 1);
+      assert_eq!(voters_data["voter:1"].token, "ABC123");
+}
+
+#[tokio::test]
+async fn migration_rewrites_legacy_reset_tokens_and_removes_unreadable_ones() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      set_voters_data(&mut redis_connection, "voter:1", "ABC123", &Campus::PD).await.unwrap();
+      let current_value: Option<String> = redis_connection.get_hash_field(VOTER_TOKEN_RESET_KEY, "voter:1");
+      redis_connection.set_hash_field(VOTER_TOKEN_RESET_KEY, "voter:2", legacy_voter_value("DEF456").as_str());
+      redis_connection.set_hash_field(VOTER_TOKEN_RESET_KEY, "voter:3", r#"{"v":2,"data":{}}"#);
+
+      migrate_values(&mut redis_connection).await.unwrap();
+
+      assert_eq!(redis_connection.get_hash_field(VOTER_TOKEN_RESET_KEY, "voter:1"), current_value);
+      let migrated_value: String = redis_connection.get_hash_field(VOTER_TOKEN_RESET_KEY, "voter:2").unwrap();
+      assert!(is_current_redis_value::<RedisVoterType>(migrated_value.as_str()));
+      assert_eq!(decode_redis_value::<RedisVoterType>(migrated_value.as_str()).unwrap().token, "DEF456");
+      assert_eq!(redis_connection.get_hash_field(VOTER_TOKEN_RESET_KEY, "voter:3"), None);
+}
+
+#[tokio::test]
+async fn admin_session_is_refreshed_until_removed() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      create_admin_session(&mut redis_connection, &SESSION_CONFIG, "admin:1", "session1").await.unwrap();
+
+      let session_data: RedisAdminSessionType = get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session1").await.unwrap().unwrap();
+      assert_eq!(session_data.admin_id, "admin:1");
+      assert_eq!(redis_connection.get_ttl(admin_session_key("session1").as_str()), Some(SESSION_CONFIG.idle_timeout));
+
+      remove_admin_session(&mut redis_connection, "session1").await.unwrap();
+
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session1").await.unwrap().is_none());
+      assert!(!redis_connection.contains_key(admin_session_key("session1").as_str()));
+      assert!(!redis_connection.contains_key(admin_sessions_key("admin:1").as_str()));
+}
+
+#[tokio::test]
+async fn admin_session_refresh_is_capped_by_the_absolute_lifetime() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      let created_at: i64 = get_timestamp() - SESSION_CONFIG.absolute_timeout + 100;
+      seed_admin_session(&mut redis_connection, "session1", &RedisAdminSessionType {
+            admin_id: String::from("admin:1"),
+            created_at,
+            last_seen_at: created_at
+      });
+
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session1").await.unwrap().is_some());
+      let ttl: i64 = redis_connection.get_ttl(admin_session_key("session1").as_str()).unwrap();
+      assert!(ttl > 0 && ttl <= 100);
+}
+
+#[tokio::test]
+async fn admin_session_past_its_absolute_lifetime_is_removed() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      let created_at: i64 = get_timestamp() - SESSION_CONFIG.absolute_timeout - 1;
+      seed_admin_session(&mut redis_connection, "session1", &RedisAdminSessionType {
+            admin_id: String::from("admin:1"),
+            created_at,
+            last_seen_at: created_at
+      });
+
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session1").await.unwrap().is_none());
+      assert!(!redis_connection.contains_key(admin_session_key("session1").as_str()));
+}
+
+#[tokio::test]
+async fn all_admin_sessions_are_removed() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      create_admin_session(&mut redis_connection, &SESSION_CONFIG, "admin:1", "session1").await.unwrap();
+      create_admin_session(&mut redis_connection, &SESSION_CONFIG, "admin:1", "session2").await.unwrap();
+      create_admin_session(&mut redis_connection, &SESSION_CONFIG, "admin:2", "session3").await.unwrap();
+
+      remove_all_admin_sessions(&mut redis_connection, "admin:1").await.unwrap();
+
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session1").await.unwrap().is_none());
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session2").await.unwrap().is_none());
+      assert!(get_admin_session(&mut redis_connection, &SESSION_CONFIG, "session3").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn login_is_locked_out_after_the_attempt_limit() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+
+      for _ in 1..LOGIN_CONFIG.attempt_limit {
+            register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Voter, "voter:1").await.unwrap();
+      }
+      assert_eq!(get_login_lockout(&mut redis_connection, LoginLimitScope::Voter, "voter:1").await.unwrap(), None);
+      assert_eq!(redis_connection.get_ttl(login_failures_key(LoginLimitScope::Voter, "voter:1").as_str()), Some(LOGIN_CONFIG.failure_window));
+
+      register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Voter, "voter:1").await.unwrap();
+      let remaining_lockout: i64 = get_login_lockout(&mut redis_connection, LoginLimitScope::Voter, "voter:1").await.unwrap().unwrap();
+      assert!(remaining_lockout > 0 && remaining_lockout <= LOGIN_CONFIG.lockout_base);
+
+      // Each failure past the limit doubles the lockout
+      register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Voter, "voter:1").await.unwrap();
+      let remaining_lockout: i64 = get_login_lockout(&mut redis_connection, LoginLimitScope::Voter, "voter:1").await.unwrap().unwrap();
+      assert!(remaining_lockout > LOGIN_CONFIG.lockout_base && remaining_lockout <= LOGIN_CONFIG.lockout_base * 2);
+
+      // Other logins aren't affected
+      assert_eq!(get_login_lockout(&mut redis_connection, LoginLimitScope::Ip, "voter:1").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn lockout_never_exceeds_the_maximum() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      redis_connection.set_string(login_failures_key(LoginLimitScope::Admin, "root").as_str(), "1000");
+
+      register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Admin, "root").await.unwrap();
+
+      let remaining_lockout: i64 = get_login_lockout(&mut redis_connection, LoginLimitScope::Admin, "root").await.unwrap().unwrap();
+      assert!(remaining_lockout > 0 && remaining_lockout <= LOGIN_CONFIG.lockout_max);
+}
+
+#[tokio::test]
+async fn cleared_login_failures_lift_the_lockout() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      for _ in 0..LOGIN_CONFIG.attempt_limit {
+            register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Ip, "10.0.0.1").await.unwrap();
+      }
+
+      clear_login_failures(&mut redis_connection, LoginLimitScope::Ip, "10.0.0.1").await.unwrap();
+
+      assert_eq!(get_login_lockout(&mut redis_connection, LoginLimitScope::Ip, "10.0.0.1").await.unwrap(), None);
+      assert!(!redis_connection.contains_key(login_failures_key(LoginLimitScope::Ip, "10.0.0.1").as_str()));
+}
+
+#[tokio::test]
+async fn only_active_lockouts_are_listed() {
+      let mut redis_connection: FakeRedisConnection = FakeRedisConnection::new();
+      for _ in 0..LOGIN_CONFIG.attempt_limit {
+            register_login_failure(&mut redis_connection, &LOGIN_CONFIG, LoginLimitScope::Ip, "10.0.0.1").await.unwrap();
+      }
+      let _: () = redis_connection.zadd(LOGIN_LOCKOUTS_KEY, login_lockout_member(LoginLimitScope::Voter, "voter:1"), get_timestamp() - 1).await.unwrap();
+      let _: () = redis_connection.zadd(LOGIN_LOCKOUTS_KEY, "unknown:key", get_timestamp() + 60).await.unwrap();
+
+      let lockouts: Vec<RedisLoginLockoutType> = get_all_login_lockouts(&mut redis_connection).await.unwrap();
+
+      assert_eq!(lockouts.len(), 1);
+      assert_eq!(lockouts[0].scope, LoginLimitScope::Ip);
+      assert_eq!(lockouts[0].key, "10.0.0.1");
+      assert!(lockouts[0].locked_until > get_timestamp());
+}
@@ -0,0 +1,112 @@
+use actix_web::{HttpResponse, delete, get, post, web};
+use deadpool_redis::Pool as RedisPool;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::admin::get_all_admin_data,
+      db::{Admin, AdminRole, Campus, remove_admin, upsert_admin},
+      error::{AppError, AppResult},
+      rdb::remove_all_admin_sessions_redis,
+      util::{hash_password, log_error, log_something},
+};
+
+#[derive(Serialize)]
+struct AdminResponseType {
+      admin_id: String,
+      role: AdminRole,
+      campus: Option<Campus>
+}
+
+#[derive(Deserialize)]
+struct AdminBodyRequestType {
+      admin_id: String,
+      admin_password: String,
+      role: AdminRole,
+      campus: Option<Campus>
+}
+
+
+#[get("/admin/admins")]
+pub async fn get(admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      admin.require_role(AdminRole::Superadmin)?;
+
+      // Map the static admin data without the password
+      let static_admin_data = get_all_admin_data();
+      let locked_static_admin_data = static_admin_data.read().await;
+      let result: Vec<AdminResponseType> = locked_static_admin_data
+            .values()
+            .map(|data| AdminResponseType {
+                  admin_id: data.admin_id.clone(),
+                  role: data.role,
+                  campus: data.campus
+            })
+            .collect();
+
+      Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/admin/admins")]
+pub async fn post(body: web::Json<AdminBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      // Operators must be assigned to a campus
+      let body = body.into_inner();
+      if body.role == AdminRole::Operator && body.campus.is_none() {
+            return Err(AppError::Validation(String::from("Operators must be assigned to a campus.")));
+      }
+
+      // Hash the password
+      let admin_password_hash: String = match hash_password(body.admin_password.as_str()) {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("PostAdmins", format!("There's an error when trying to hash admin password. Error: {}", err).as_str());
+                  return Err(AppError::from(err));
+            }
+      };
+
+      // Create or replace the admin
+      let upsert_result = upsert_admin(Admin {
+            admin_id: body.admin_id.clone(),
+            admin_password: admin_password_hash,
+            role: body.role,
+            campus: body.campus
+      }).await;
+
+      match upsert_result {
+            Ok(_) => {
+                  log_something("PostAdmins", format!("{} has saved the admin {}", admin_data.admin_id, body.admin_id).as_str());
+            },
+            Err(err) => {
+                  log_error("PostAdmins", format!("There's an error when trying to save admin. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/admin/admins/{admin_id}")]
+pub async fn delete(path: web::Path<String>, admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      // Prevent the admin from locking themselves out
+      let target_admin_id: String = path.into_inner();
+      if target_admin_id == admin_data.admin_id {
+            return Err(AppError::Validation(String::from("Admins can't remove themselves.")));
+      }
+
+      // Remove the admin and every session of it
+      if let Err(err) = remove_admin(target_admin_id.as_str()).await {
+            log_error("DeleteAdmins", format!("There's an error when trying to remove admin. Error: {}", err).as_str());
+            return Err(err);
+      }
+
+      remove_all_admin_sessions_redis(&redis_pool, target_admin_id.as_str()).await?;
+
+      log_something("DeleteAdmins", format!("{} has removed the admin {}", admin_data.admin_id, target_admin_id).as_str());
+      Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,192 @@
+use actix_web::{HttpResponse, delete, get, post, web};
+use serde::Deserialize;
+use surrealdb::Uuid;
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::{candidate::get_all_candidates_data, election::get_election_state},
+      db::{Admin, AdminRole, Campus, Candidate, ElectionState, insert_candidate, reorder_candidates, update_candidate, withdraw_candidate},
+      error::{AppError, AppResult},
+      util::{log_error, log_something},
+};
+
+#[derive(Deserialize)]
+struct CandidateBodyRequestType {
+      president: String,
+      vice_president: String,
+      campus: Campus,
+      vision: String,
+      misions: Vec<String>
+}
+
+#[derive(Deserialize)]
+struct CandidateOrderBodyRequestType {
+      campus: Campus,
+      candidate_ids: Vec<String>
+}
+
+async fn get_candidate(candidate_id: &str) -> Option<Candidate> {
+      get_all_candidates_data().read().await
+            .iter()
+            .find(|candidate| candidate.candidate_id == candidate_id)
+            .cloned()
+}
+
+/// The candidates of a campus can only be changed before its election is opened.
+async fn verify_candidates_editable(campus: &Campus) -> AppResult<()> {
+      match get_election_state(campus).await {
+            ElectionState::Draft => Ok(()),
+            _ => Err(AppError::Forbidden)
+      }
+}
+
+
+#[get("/admin/candidates")]
+pub async fn get(admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      admin.require_role(AdminRole::Superadmin)?;
+
+      // Include the withdrawn candidates
+      let result: Vec<Candidate> = get_all_candidates_data().read().await.clone();
+
+      Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/admin/candidates")]
+pub async fn post(body: web::Json<CandidateBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let body = body.into_inner();
+      verify_candidates_editable(&body.campus).await?;
+
+      // Put the new candidate at the end of the ballot
+      let position: u32 = get_all_candidates_data().read().await
+            .iter()
+            .filter(|candidate| candidate.campus == body.campus)
+            .map(|candidate| candidate.position + 1)
+            .max()
+            .unwrap_or(0);
+
+      let insert_result = insert_candidate(Candidate {
+            candidate_id: Uuid::new_v4().to_string(),
+            president: body.president.clone(),
+            vice_president: body.vice_president,
+            campus: body.campus,
+            vision: body.vision,
+            misions: body.misions,
+            position,
+            withdrawn: false
+      }).await;
+
+      match insert_result {
+            Ok(_) => {
+                  log_something("PostCandidates", format!("{} has created the candidate {}", admin_data.admin_id, body.president).as_str());
+            },
+            Err(err) => {
+                  log_error("PostCandidates", format!("There's an error when trying to create candidate. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/admin/candidates/order")]
+pub async fn order(body: web::Json<CandidateOrderBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let body = body.into_inner();
+      verify_candidates_editable(&body.campus).await?;
+
+      // Every candidate of the campus must be listed exactly once
+      let mut campus_candidate_ids: Vec<String> = get_all_candidates_data().read().await
+            .iter()
+            .filter(|candidate| candidate.campus == body.campus && !candidate.withdrawn)
+            .map(|candidate| candidate.candidate_id.clone())
+            .collect();
+      let mut body_candidate_ids: Vec<String> = body.candidate_ids.clone();
+      campus_candidate_ids.sort();
+      body_candidate_ids.sort();
+
+      if campus_candidate_ids != body_candidate_ids {
+            return Err(AppError::Validation(String::from("Every candidate of the campus must be listed exactly once.")));
+      }
+
+      match reorder_candidates(body.candidate_ids).await {
+            Ok(_) => {
+                  log_something("PostCandidatesOrder", format!("{} has reordered the candidates of {}", admin_data.admin_id, body.campus.as_str()).as_str());
+            },
+            Err(err) => {
+                  log_error("PostCandidatesOrder", format!("There's an error when trying to reorder candidates. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/admin/candidates/{candidate_id}")]
+pub async fn update(path: web::Path<String>, body: web::Json<CandidateBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let target_candidate_id: String = path.into_inner();
+      let candidate_data: Candidate = match get_candidate(target_candidate_id.as_str()).await {
+            Some(data) => data,
+            None => {
+                  return Err(AppError::NotFound);
+            }
+      };
+
+      // Both of the campus must still be editable when the candidate is moved
+      let body = body.into_inner();
+      verify_candidates_editable(&candidate_data.campus).await?;
+      verify_candidates_editable(&body.campus).await?;
+
+      let update_result = update_candidate(Candidate {
+            president: body.president,
+            vice_president: body.vice_president,
+            campus: body.campus,
+            vision: body.vision,
+            misions: body.misions,
+            ..candidate_data
+      }).await;
+
+      match update_result {
+            Ok(_) => {
+                  log_something("PostCandidate", format!("{} has updated the candidate {}", admin_data.admin_id, candidate_data.president).as_str());
+            },
+            Err(err) => {
+                  log_error("PostCandidate", format!("There's an error when trying to update candidate. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/admin/candidates/{candidate_id}")]
+pub async fn delete(path: web::Path<String>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let target_candidate_id: String = path.into_inner();
+      let candidate_data: Candidate = match get_candidate(target_candidate_id.as_str()).await {
+            Some(data) => data,
+            None => {
+                  return Err(AppError::NotFound);
+            }
+      };
+
+      verify_candidates_editable(&candidate_data.campus).await?;
+
+      // Keep the record so the existing votes and receipts still refer to it
+      match withdraw_candidate(target_candidate_id.as_str()).await {
+            Ok(_) => {
+                  log_something("DeleteCandidate", format!("{} has withdrawn the candidate {}", admin_data.admin_id, candidate_data.president).as_str());
+            },
+            Err(err) => {
+                  log_error("DeleteCandidate", format!("There's an error when trying to withdraw candidate. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
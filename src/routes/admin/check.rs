@@ -1,21 +1,9 @@
-use actix_web::{HttpRequest, HttpResponse, post};
+use actix_web::{HttpResponse, post};
 
-use crate::util::verify_admin_token;
+use crate::auth::AuthenticatedAdmin;
 
 #[post("/admin/check")]
-pub async fn post(req: HttpRequest) -> HttpResponse {
-      // Get the admin token from request cookies
-      let cookie_admin_token = req.cookie("admin_session_token");
-      let cookie_admin_token = match cookie_admin_token {
-          Some(data) => data.to_string(),
-          None => {
-              return HttpResponse::Unauthorized().finish();
-          }
-      };
-
-      // Check the admin token
-      match verify_admin_token(cookie_admin_token.as_str()).await {
-            Ok(_) => HttpResponse::Ok().finish(),
-            Err(response) => response
-      }
+pub async fn post(_admin: AuthenticatedAdmin) -> HttpResponse {
+      // The admin session is verified by the extractor
+      HttpResponse::Ok().finish()
 }
@@ -0,0 +1,109 @@
+use actix_web::{HttpResponse, post, web};
+use serde::Deserialize;
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::election::get_elections_data,
+      db::{Admin, AdminRole, Campus, Election, ElectionState, upsert_election},
+      error::{AppError, AppResult},
+      util::{get_timestamp, log_error, log_something},
+};
+
+#[derive(Deserialize)]
+struct ElectionStateBodyRequestType {
+      campus: Campus,
+      state: ElectionState
+}
+
+#[derive(Deserialize)]
+struct ElectionScheduleBodyRequestType {
+      campus: Campus,
+      starts_at: Option<i64>,
+      ends_at: Option<i64>
+}
+
+async fn get_election(campus: &Campus) -> Election {
+      let elections_data = get_elections_data();
+      let locked_elections_data = elections_data.read().await;
+
+      locked_elections_data.get(campus).cloned().unwrap_or(Election::new(*campus))
+}
+
+async fn save_election(election: Election) -> AppResult<()> {
+      // Update the static data right away, the live query will do the same later
+      {
+            let elections_data = get_elections_data();
+            let mut locked_write_elections_data = elections_data.write().await;
+            locked_write_elections_data.insert(election.campus, election.clone());
+      }
+
+      match upsert_election(election).await {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                  log_error("PostElection", format!("There's an error when trying to save the election. Error: {}", err).as_str());
+                  Err(err)
+            }
+      }
+}
+
+
+#[post("/admin/election/state")]
+pub async fn state(body: web::Json<ElectionStateBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      // Verify the state transition
+      let body = body.into_inner();
+      let election: Election = get_election(&body.campus).await;
+      let current_state: ElectionState = election.get_state_at(get_timestamp());
+      let is_allowed: bool = match (current_state, body.state) {
+            (_, ElectionState::Draft) => false,
+            (ElectionState::Closed, _) => false,
+            (ElectionState::Draft | ElectionState::Paused, ElectionState::Open) => true,
+            (ElectionState::Open, ElectionState::Paused) => true,
+            (_, ElectionState::Closed) => true,
+            (current_state, target_state) => current_state == target_state,
+      };
+
+      if !is_allowed {
+            return Err(AppError::Conflict(format!("The election can't be set from {} to {}.", current_state.as_str(), body.state.as_str())));
+      }
+
+      // Save the new state
+      save_election(Election {
+            state: body.state,
+            ..election
+      }).await?;
+
+      log_something("PostElection", format!("{} has set the election of {} to {}", admin_data.admin_id, body.campus.as_str(), body.state.as_str()).as_str());
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[post("/admin/election/schedule")]
+pub async fn schedule(body: web::Json<ElectionScheduleBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      // Verify the schedule
+      let body = body.into_inner();
+      if let (Some(starts_at), Some(ends_at)) = (body.starts_at, body.ends_at)
+            && starts_at >= ends_at
+      {
+            return Err(AppError::Validation(String::from("The election must start before it ends.")));
+      }
+
+      let election: Election = get_election(&body.campus).await;
+      if election.get_state_at(get_timestamp()) == ElectionState::Closed {
+            return Err(AppError::Conflict(String::from("The election is already closed.")));
+      }
+
+      // Save the new schedule
+      save_election(Election {
+            starts_at: body.starts_at,
+            ends_at: body.ends_at,
+            ..election
+      }).await?;
+
+      log_something("PostElection", format!("{} has scheduled the election of {}", admin_data.admin_id, body.campus.as_str()).as_str());
+      Ok(HttpResponse::Ok().finish())
+}
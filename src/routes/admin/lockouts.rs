@@ -0,0 +1,59 @@
+use actix_web::{HttpResponse, delete, get, web};
+use deadpool_redis::Pool as RedisPool;
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::voter::get_voters_data,
+      db::{Admin, AdminRole, Campus},
+      error::{AppError, AppResult},
+      rdb::{LoginLimitScope, RedisLoginLockoutType, clear_login_failures_redis, get_all_login_lockouts_redis},
+      util::log_something,
+};
+
+/// Operators only see the lockouts of the voters of their campus, the IP and admin lockouts aren't tied to one.
+async fn can_manage_lockout(admin_data: &Admin, scope: LoginLimitScope, key: &str) -> bool {
+      if admin_data.has_role(AdminRole::Superadmin) {
+            return true;
+      }
+      if scope != LoginLimitScope::Voter {
+            return false;
+      }
+
+      let voter_campus: Option<Campus> = get_voters_data().read().await.get(key).map(|voter| voter.campus);
+      voter_campus.is_some_and(|campus| admin_data.can_manage_campus(&campus))
+}
+
+
+#[get("/admin/lockouts")]
+pub async fn get(admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Operator)?;
+
+      // Get the currently locked logins the admin manages
+      let mut lockouts: Vec<RedisLoginLockoutType> = Vec::new();
+      for lockout in get_all_login_lockouts_redis(&redis_pool).await? {
+            if can_manage_lockout(&admin_data, lockout.scope, lockout.key.as_str()).await {
+                  lockouts.push(lockout);
+            }
+      }
+
+      Ok(HttpResponse::Ok().json(lockouts))
+}
+
+#[delete("/admin/lockouts/{scope}/{key}")]
+pub async fn delete(path: web::Path<(LoginLimitScope, String)>, admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Operator)?;
+
+      // Only superadmins can unlock admin accounts, IPs and the voters outside of the campus of an operator
+      let (scope, key) = path.into_inner();
+      if !can_manage_lockout(&admin_data, scope, key.as_str()).await {
+            return Err(AppError::Forbidden);
+      }
+
+      // Unlock the login
+      clear_login_failures_redis(&redis_pool, scope, key.as_str()).await?;
+      log_something("DeleteLockouts", format!("{} has unlocked {} {}", admin_data.admin_id, scope.as_str(), key).as_str());
+
+      Ok(HttpResponse::Ok().finish())
+}
@@ -1,11 +1,19 @@
-use actix_web::{HttpResponse, cookie::Cookie, post, web};
+use actix_web::{HttpResponse, middleware::from_fn, post, web};
+use deadpool_redis::Pool as RedisPool;
+use once_cell::sync::Lazy;
 use serde::Deserialize;
 use rand::{Rng, distr::Alphanumeric};
 use time::Duration;
 
-use crate::{data::admin::get_all_admin_data, db::set_admin_session_token, util::log_error};
+use crate::{auth::{ADMIN_SESSION_COOKIE, build_cookie}, config::get_config, data::admin::get_all_admin_data, db::set_admin_password, error::{AppError, AppResult}, middleware::login_rate_limit, rdb::{LoginLimitScope, clear_login_failures_redis, create_admin_session_redis, get_login_lockout_redis, register_login_failure_redis}, util::{PasswordCheck, hash_password, log_error, log_something, verify_password}};
 
-pub static TOKEN_LEN:usize = 50;
+static DUMMY_PASSWORD: &str = "kprs-dummy-admin-password";
+
+// An unknown Admin ID is still verified against an Argon2 hash with the same parameters as the stored ones, so it
+// takes as long as a wrong password and the response time doesn't tell whether the admin exists. Hashed once.
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+      hash_password(DUMMY_PASSWORD).unwrap_or_default()
+});
 
 #[derive(Deserialize)]
 struct AdminLoginData {
@@ -14,14 +22,19 @@ struct AdminLoginData {
 }
 
 
-#[post("/admin/login")]
-pub async fn post(data: web::Json<AdminLoginData>) -> HttpResponse {
+#[post("/admin/login", wrap = "from_fn(login_rate_limit)")]
+pub async fn post(data: web::Json<AdminLoginData>, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
       // Get Admin ID and Admin Password
       let data = data.into_inner();
 
+      // Check if the admin is locked out from too many failed attempts
+      if let Some(remaining_lockout) = get_login_lockout_redis(&redis_pool, LoginLimitScope::Admin, data.admin_id.as_str()).await? {
+            return Err(AppError::TooManyRequests(remaining_lockout));
+      }
+
       // Get static admin data
       let static_admin_data = get_all_admin_data();
-      {
+      let password_check: PasswordCheck = {
             // Read static admin data
             let locked_static_admin_data = static_admin_data.read().await;
             let target_admin_data = locked_static_admin_data.get(&data.admin_id);
@@ -30,45 +43,63 @@ pub async fn post(data: web::Json<AdminLoginData>) -> HttpResponse {
             let target_admin_data = match target_admin_data {
                   Some(data) => data,
                   None => {
-                        return HttpResponse::Unauthorized().finish();
+                        let _ = verify_password(data.admin_password.as_str(), DUMMY_PASSWORD_HASH.as_str());
+                        return Err(AppError::Unauthorized);
                   }
             };
 
             // Check if the the Admin Password correct
-            if target_admin_data.admin_password != data.admin_password {
-                  return HttpResponse::Unauthorized().finish();
+            verify_password(data.admin_password.as_str(), target_admin_data.admin_password.as_str())
+      };
+
+      match password_check {
+            PasswordCheck::Valid => (),
+            PasswordCheck::ValidLegacy => {
+                  // Upgrade the plaintext password into a hash
+                  match hash_password(data.admin_password.as_str()) {
+                        Ok(admin_password_hash) => {
+                              {
+                                    let mut write_locked_static_admin_data = static_admin_data.write().await;
+                                    write_locked_static_admin_data.entry(data.admin_id.clone()).and_modify(|data| {
+                                          data.admin_password = admin_password_hash.clone();
+                                    });
+                              }
+
+                              match set_admin_password(data.admin_id.as_str(), admin_password_hash).await {
+                                    Ok(_) => {
+                                          log_something("AdminLogin", format!("Upgraded the plaintext password of {} into a hash.", data.admin_id).as_str());
+                                    },
+                                    Err(err) => {
+                                          log_error("AdminLogin", format!("There's an error when trying to upgrade admin password. Error: {}", err).as_str());
+                                    }
+                              }
+                        },
+                        Err(err) => {
+                              log_error("AdminLogin", format!("There's an error when trying to hash admin password. Error: {}", err).as_str());
+                        }
+                  }
+            },
+            PasswordCheck::Invalid => {
+                  register_login_failure_redis(&redis_pool, LoginLimitScope::Admin, data.admin_id.as_str()).await?;
+                  return Err(AppError::Unauthorized);
             }
       }
 
+      clear_login_failures_redis(&redis_pool, LoginLimitScope::Admin, data.admin_id.as_str()).await?;
+
       // Create admin cookie
       let mut rng = rand::rng();
-      let admin_session_token: String = (0..TOKEN_LEN)
+      let admin_session_token: String = (0..get_config().token.admin_session_length)
             .map(|_| rng.sample(Alphanumeric) as char)
             .collect::<String>();
 
-      {
-            // Update static admin data
-            let mut write_locked_static_admin_data = static_admin_data.write().await;
-            write_locked_static_admin_data.entry(data.admin_id.clone()).and_modify(|data| {
-                  data.admin_session_token = Some(admin_session_token.clone().to_string());
-            });
-      }
-
-      // Update from database
-      match set_admin_session_token(data.admin_id.as_str(), admin_session_token.as_str()).await {
-            Ok(_) => (),
-            Err(err) => {
-                  log_error("AdminLogin", format!("There's an error when trying to set admin session token. Error: {}", err.to_string()).as_str());
-            }
-      }
+      // Store the admin session
+      create_admin_session_redis(&redis_pool, data.admin_id.as_str(), admin_session_token.as_str()).await?;
 
       // Create admin session token cookie
-      let admin_session_token_cookie = Cookie::build("admin_session_token", admin_session_token.as_str())
-            .path("/")
-            .secure(true)
-            .http_only(true)
-            .max_age(Duration::days(2))
+      let admin_session_token_cookie = build_cookie(ADMIN_SESSION_COOKIE, admin_session_token.as_str())
+            .max_age(Duration::seconds(get_config().session.absolute_timeout))
             .finish();
 
-      HttpResponse::Ok().cookie(admin_session_token_cookie).finish()
+      Ok(HttpResponse::Ok().cookie(admin_session_token_cookie).finish())
 }
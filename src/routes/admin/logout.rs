@@ -0,0 +1,17 @@
+use actix_web::{HttpResponse, post, web};
+use deadpool_redis::Pool as RedisPool;
+use time::Duration;
+
+use crate::{auth::{ADMIN_SESSION_COOKIE, AuthenticatedAdmin, build_cookie}, error::AppResult, rdb::remove_admin_session_redis};
+
+#[post("/admin/logout")]
+pub async fn post(admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      // Revoke the current session
+      remove_admin_session_redis(&redis_pool, admin.session_token.as_str()).await?;
+
+      let clear_cookie = build_cookie(ADMIN_SESSION_COOKIE, "")
+            .max_age(Duration::seconds(0))
+            .finish();
+
+      Ok(HttpResponse::Ok().cookie(clear_cookie).finish())
+}
@@ -0,0 +1,20 @@
+use actix_web::{HttpResponse, post, web};
+use deadpool_redis::Pool as RedisPool;
+use time::Duration;
+
+use crate::{auth::{ADMIN_SESSION_COOKIE, AuthenticatedAdmin, build_cookie}, db::Admin, error::AppResult, rdb::remove_all_admin_sessions_redis, util::log_something};
+
+#[post("/admin/logout-all")]
+pub async fn post(admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.admin;
+
+      // Revoke every session of the admin
+      remove_all_admin_sessions_redis(&redis_pool, admin_data.admin_id.as_str()).await?;
+      log_something("AdminLogout", format!("All sessions of {} has been revoked.", admin_data.admin_id).as_str());
+
+      let clear_cookie = build_cookie(ADMIN_SESSION_COOKIE, "")
+            .max_age(Duration::seconds(0))
+            .finish();
+
+      Ok(HttpResponse::Ok().cookie(clear_cookie).finish())
+}
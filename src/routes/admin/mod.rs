@@ -4,6 +4,13 @@ mod votes;
 mod login;
 mod check;
 mod simple_votes;
+mod logout;
+mod logout_all;
+mod admins;
+mod lockouts;
+mod election;
+mod candidates;
+mod voters;
 
 pub use self::token::get as admin_token_api;
 pub use self::reset::post as admin_reset_api;
@@ -11,3 +18,22 @@ pub use self::votes::get as admin_votes_api;
 pub use self::login::post as admin_login_api;
 pub use self::check::post as admin_check_api;
 pub use self::simple_votes::post as admin_votes_simple_api;
+pub use self::logout::post as admin_logout_api;
+pub use self::logout_all::post as admin_logout_all_api;
+pub use self::admins::get as admin_admins_get_api;
+pub use self::admins::post as admin_admins_post_api;
+pub use self::admins::delete as admin_admins_delete_api;
+pub use self::lockouts::get as admin_lockouts_get_api;
+pub use self::lockouts::delete as admin_lockouts_delete_api;
+pub use self::election::state as admin_election_state_api;
+pub use self::election::schedule as admin_election_schedule_api;
+pub use self::candidates::get as admin_candidates_get_api;
+pub use self::candidates::post as admin_candidates_post_api;
+pub use self::candidates::order as admin_candidates_order_api;
+pub use self::candidates::update as admin_candidates_update_api;
+pub use self::candidates::delete as admin_candidates_delete_api;
+pub use self::voters::get as admin_voters_get_api;
+pub use self::voters::post as admin_voters_post_api;
+pub use self::voters::import as admin_voters_import_api;
+pub use self::voters::update as admin_voters_update_api;
+pub use self::voters::delete as admin_voters_delete_api;
@@ -1,48 +1,25 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
-use actix_web::{HttpRequest, HttpResponse, get};
-use tokio::sync::RwLock;
+use actix_web::{HttpResponse, get};
+use strum::IntoEnumIterator;
 
-use crate::{data::vote::get_votes_count, db::Campus, util::verify_admin_token};
+use crate::{auth::AuthenticatedAdmin, data::{election::is_results_embargoed, vote::{get_votes_tally, is_secret_ballot}}, db::{Admin, AdminRole, Campus}};
 
 
 #[get("/admin/votes/simple")]
-pub async fn post(req: HttpRequest) -> HttpResponse {
-      // Get the admin token from request cookies
-      let cookie_admin_token = req.cookie("admin_session_token");
-      let cookie_admin_token = match cookie_admin_token {
-          Some(data) => data.value().to_string(),
-          None => {
-              return HttpResponse::Unauthorized().finish();
-          }
-      };
-
-      // Verify the admin token
-      match verify_admin_token(cookie_admin_token.as_str()).await {
-            Ok(_) => (),
-            Err(response) => return response
-      };
-
-      // Get the static votes data
-      let static_votes_data: Arc<HashMap<Campus, RwLock<HashMap<String, String>>>> = get_votes_count();
-
-
-      // Map the result to each candidates
+pub async fn post(admin: AuthenticatedAdmin) -> HttpResponse {
+      let admin_data: Admin = admin.admin;
+
+
+      // Map the result to each candidates, embargoed campuses are only visible to superadmins outside of secret ballot mode
+      let can_see_embargoed: bool = admin_data.has_role(AdminRole::Superadmin) && !is_secret_ballot();
       let mut vote_result: HashMap<Campus, HashMap<String, usize>> = HashMap::new();
-      for static_votes_data_per_campus in static_votes_data.iter() {
-            let locked_static_votes_data = static_votes_data_per_campus.1.read().await;
-
-            let mut vote_per_campus_result: HashMap<String, usize> = HashMap::new();
-            for (_, candidate_name) in locked_static_votes_data.clone() {
-                  vote_per_campus_result
-                        .entry(candidate_name.clone())
-                        .and_modify(|counter| {
-                              *counter += 1;
-                        })
-                        .or_insert(1);
+      for campus in Campus::iter() {
+            if !can_see_embargoed && is_results_embargoed(&campus).await {
+                  continue;
             }
 
-            vote_result.insert(static_votes_data_per_campus.0.clone(), vote_per_campus_result);
+            vote_result.insert(campus, get_votes_tally(&campus).await);
       }
 
 
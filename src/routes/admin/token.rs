@@ -1,38 +1,27 @@
 use std::{collections::HashMap, sync::Arc};
-use actix_web::{HttpRequest, HttpResponse, get, web::{self, Json}};
+use actix_web::{HttpResponse, get, web::{self, Json}};
 use deadpool_redis::{Pool as RedisPool};
+use serde::Serialize;
 use strum::IntoEnumIterator;
 use tokio::sync::RwLock;
 
-use crate::{data::voter::get_voters_data, db::{Campus, Voter}, rdb::{RedisVoterType, get_voters_data_redis}, util::{log_error, verify_admin_token}};
+use crate::{auth::AuthenticatedAdmin, data::voter::get_voters_data, db::{Admin, AdminRole, Campus, Voter}, error::AppResult, rdb::{RedisVoterType, get_voters_data_redis}};
+
+#[derive(Serialize)]
+struct VoterTokenResponseType {
+      name: String,
+      token: String
+}
 
 
 #[get("/admin/token")]
-pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResponse {
-      // Get the admin token from request cookies
-      let cookie_admin_token = req.cookie("admin_session_token");
-      let cookie_admin_token = match cookie_admin_token {
-          Some(data) => data.value().to_string(),
-          None => {
-              return HttpResponse::Unauthorized().finish();
-          }
-      };
-
-      // Verify the admin token
-      match verify_admin_token(cookie_admin_token.as_str()).await {
-            Ok(_) => (),
-            Err(response) => return response
-      }
+pub async fn get(admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      let admin_data: Admin = admin.require_role(AdminRole::Operator)?;
 
 
       // Get the token data from Redis
-      let redis_voter_tokens: Result<HashMap<String, RedisVoterType>, HttpResponse>  = get_voters_data_redis(&redis_pool).await;
-      let redis_voter_tokens: HashMap<String, RedisVoterType> = match redis_voter_tokens {
-            Ok(data) => data,
-            Err(err) => {
-                  return err;
-            }
-      };
+      let redis_voter_tokens: HashMap<String, RedisVoterType> = get_voters_data_redis(&redis_pool).await?;
 
 
       // Get the token data from static
@@ -41,22 +30,25 @@ pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResp
 
 
       // Map all of the result into a single variable
-      let mut result_voters_token: HashMap<Campus, HashMap<String, String>> = HashMap::new();
+      let mut result_voters_token: HashMap<Campus, HashMap<String, VoterTokenResponseType>> = HashMap::new();
 
-      for campus_name in Campus::iter() {
+      for campus_name in Campus::iter().filter(|campus_name| admin_data.can_manage_campus(campus_name)) {
             result_voters_token.insert(campus_name, HashMap::new());
       }
 
-      for static_voter_token in locked_static_voter_tokens.iter() {
+      for static_voter_token in locked_static_voter_tokens.iter().filter(|data| !data.1.deactivated) {
             let result_voters_token_per_campus = result_voters_token.get_mut(&static_voter_token.1.campus);
             let result_voters_token_per_campus = match result_voters_token_per_campus {
                   Some(data) => data,
                   None => {
-                        log_error("GetToken", "There's a voter data in static data where the campus is not in the enum!");
+                        // The admin isn't allowed to see the voters of this campus
                         continue;
                   }
             };
-            result_voters_token_per_campus.insert(static_voter_token.0.clone(), static_voter_token.1.token.clone());
+            result_voters_token_per_campus.insert(static_voter_token.0.clone(), VoterTokenResponseType {
+                  name: static_voter_token.1.name.clone(),
+                  token: static_voter_token.1.token.clone()
+            });
       }
 
       for dynamic_voter_token in redis_voter_tokens.iter() {
@@ -64,14 +56,13 @@ pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResp
             let result_voters_token_by_campus = match result_voters_token_by_campus {
                   Some(data) => data,
                   None => {
-                        log_error("GetToken", "There's a voter data in redis where the campus is not in the enum!");
                         continue;
                   }
             };
 
             result_voters_token_by_campus.entry(dynamic_voter_token.0.clone())
                 .and_modify(|result_voter_token| {
-                      *result_voter_token = dynamic_voter_token.1.token.clone();
+                      result_voter_token.token = dynamic_voter_token.1.token.clone();
                 });
       }
 
@@ -79,5 +70,5 @@ pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResp
       // Return the token data
       let mut response = HttpResponse::Ok();
 
-      response.json(Json(result_voters_token))
+      Ok(response.json(Json(result_voters_token)))
 }
@@ -0,0 +1,384 @@
+use std::collections::HashSet;
+
+use actix_web::{HttpResponse, delete, get, post, web};
+use deadpool_redis::Pool as RedisPool;
+use serde::{Deserialize, Serialize};
+use surrealdb::Uuid;
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::voter::get_voters_data,
+      db::{Admin, AdminRole, Campus, Voter, deactivate_voter, update_voter},
+      error::{AppError, AppResult},
+      util::{insert_voters_with_new_tokens, log_error, log_something},
+};
+
+#[derive(Serialize)]
+struct VoterResponseType {
+      voter_id: String,
+      name: String,
+      class: String,
+      campus: Campus,
+      deactivated: bool
+}
+
+#[derive(Deserialize)]
+struct VoterBodyRequestType {
+      voter_id: Option<String>,
+      name: String,
+      class: String,
+      campus: Campus
+}
+
+#[derive(Deserialize)]
+struct VoterImportQueryType {
+      #[serde(default)]
+      dry_run: bool
+}
+
+/// A row of the imported CSV, the campus is checked separately to report unknown campuses.
+#[derive(Deserialize)]
+struct VoterImportRowType {
+      name: String,
+      class: String,
+      campus: String,
+      #[serde(default)]
+      student_number: Option<String>
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum VoterImportErrorType {
+      Malformed,
+      UnknownCampus,
+      Duplicate
+}
+
+#[derive(Serialize)]
+struct VoterImportRowErrorType {
+      line: u64,
+      error: VoterImportErrorType,
+      message: String
+}
+
+#[derive(Serialize)]
+struct VoterImportResponseType {
+      dry_run: bool,
+      imported: usize,
+      errors: Vec<VoterImportRowErrorType>
+}
+
+impl From<&Voter> for VoterResponseType {
+      fn from(voter: &Voter) -> Self {
+            VoterResponseType {
+                  voter_id: voter.voter_id.clone(),
+                  name: voter.name.clone(),
+                  class: voter.class.clone(),
+                  campus: voter.campus,
+                  deactivated: voter.deactivated
+            }
+      }
+}
+
+async fn get_voter(voter_id: &str) -> Option<Voter> {
+      get_voters_data().read().await.get(voter_id).cloned()
+}
+
+/// Two voters with the same name, class and campus are the same voter, even with different student numbers.
+fn get_voter_identity(name: &str, class: &str, campus: &Campus) -> String {
+      format!("{}\n{}\n{}", name.to_lowercase(), class.to_lowercase(), campus.as_str())
+}
+
+fn is_blank(data: &str) -> bool {
+      data.trim().is_empty()
+}
+
+/// The name, class and the student number when it's given can't be blank.
+fn validate_voter_body(body: &VoterBodyRequestType) -> AppResult<()> {
+      if is_blank(body.name.as_str()) || is_blank(body.class.as_str()) {
+            return Err(AppError::Validation(String::from("The name and class can't be empty.")));
+      }
+      if body.voter_id.as_deref().is_some_and(is_blank) {
+            return Err(AppError::Validation(String::from("The student number can't be empty.")));
+      }
+
+      Ok(())
+}
+
+
+#[get("/admin/voters")]
+pub async fn get(admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Operator)?;
+
+      // Only the voters of the campus the admin manages, without the token
+      let result: Vec<VoterResponseType> = get_voters_data().read().await
+            .values()
+            .filter(|voter| admin_data.can_manage_campus(&voter.campus))
+            .map(VoterResponseType::from)
+            .collect();
+
+      Ok(HttpResponse::Ok().json(result))
+}
+
+#[post("/admin/voters")]
+pub async fn post(body: web::Json<VoterBodyRequestType>, admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let body = body.into_inner();
+      validate_voter_body(&body)?;
+
+      // The student number must be unique
+      let voter_id: String = body.voter_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+      if get_voter(voter_id.as_str()).await.is_some() {
+            return Err(AppError::Conflict(String::from("The student number is already used by another voter.")));
+      }
+
+      let voter: Voter = Voter {
+            voter_id,
+            token: String::new(),
+            name: body.name,
+            class: body.class,
+            campus: body.campus,
+            deactivated: false
+      };
+
+      let voter: Voter = match insert_voters_with_new_tokens(&redis_pool, vec![voter]).await.map(|mut voters| voters.pop()) {
+            Ok(Some(voter)) => {
+                  log_something("PostVoters", format!("{} has created the voter {}", admin_data.admin_id, voter.voter_id).as_str());
+                  voter
+            },
+            Ok(None) => {
+                  return Err(AppError::Internal(String::from("No voter was created.")));
+            },
+            Err(err) => {
+                  log_error("PostVoters", format!("There's an error when trying to create voter. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      };
+
+      // The token is only returned once, the same as a reset
+      Ok(HttpResponse::Ok().json(voter))
+}
+
+#[post("/admin/voters/import")]
+pub async fn import(query: web::Query<VoterImportQueryType>, body: String, admin: AuthenticatedAdmin, redis_pool: web::Data<RedisPool>) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      // Compare against the existing voters
+      let mut used_student_numbers: HashSet<String> = HashSet::new();
+      let mut used_identities: HashSet<String> = HashSet::new();
+      for voter in get_voters_data().read().await.values() {
+            used_student_numbers.insert(voter.voter_id.clone());
+            used_identities.insert(get_voter_identity(voter.name.as_str(), voter.class.as_str(), &voter.campus));
+      }
+
+      // Check every row before importing any of them
+      let mut csv_reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .flexible(true)
+            .from_reader(body.as_bytes());
+      let csv_headers = match csv_reader.headers() {
+            Ok(data) => data.clone(),
+            Err(err) => {
+                  return Ok(HttpResponse::BadRequest().json(VoterImportResponseType {
+                        dry_run: query.dry_run,
+                        imported: 0,
+                        errors: vec![VoterImportRowErrorType {
+                              line: 1,
+                              error: VoterImportErrorType::Malformed,
+                              message: err.to_string()
+                        }]
+                  }));
+            }
+      };
+      // Voters only get a generated ID when the roster has no student number column
+      let has_student_numbers: bool = csv_headers.iter().any(|header| header == "student_number");
+
+      let mut import_rows: Vec<(VoterImportRowType, Campus)> = Vec::new();
+      let mut import_errors: Vec<VoterImportRowErrorType> = Vec::new();
+      for record in csv_reader.records() {
+            let record = match record {
+                  Ok(data) => data,
+                  Err(err) => {
+                        import_errors.push(VoterImportRowErrorType {
+                              line: err.position().map(|position| position.line()).unwrap_or(0),
+                              error: VoterImportErrorType::Malformed,
+                              message: err.to_string()
+                        });
+                        continue;
+                  }
+            };
+            let line: u64 = record.position().map(|position| position.line()).unwrap_or(0);
+
+            let row: VoterImportRowType = match record.deserialize::<VoterImportRowType>(Some(&csv_headers)) {
+                  Ok(data) => data,
+                  Err(err) => {
+                        import_errors.push(VoterImportRowErrorType {
+                              line,
+                              error: VoterImportErrorType::Malformed,
+                              message: err.to_string()
+                        });
+                        continue;
+                  }
+            };
+
+            if is_blank(row.name.as_str()) || is_blank(row.class.as_str()) {
+                  import_errors.push(VoterImportRowErrorType {
+                        line,
+                        error: VoterImportErrorType::Malformed,
+                        message: String::from("The name and class can't be empty")
+                  });
+                  continue;
+            }
+
+            if has_student_numbers && row.student_number.as_deref().is_none_or(is_blank) {
+                  import_errors.push(VoterImportRowErrorType {
+                        line,
+                        error: VoterImportErrorType::Malformed,
+                        message: String::from("The student number can't be empty")
+                  });
+                  continue;
+            }
+
+            let campus: Campus = match Campus::parse(row.campus.as_str()) {
+                  Some(data) => data,
+                  None => {
+                        import_errors.push(VoterImportRowErrorType {
+                              line,
+                              error: VoterImportErrorType::UnknownCampus,
+                              message: format!("{} isn't a campus", row.campus)
+                        });
+                        continue;
+                  }
+            };
+
+            // Both the student number and the name, class and campus must be new
+            if let Some(student_number) = &row.student_number
+                  && !used_student_numbers.insert(student_number.clone()) {
+                  import_errors.push(VoterImportRowErrorType {
+                        line,
+                        error: VoterImportErrorType::Duplicate,
+                        message: format!("The student number {} already exists", student_number)
+                  });
+                  continue;
+            }
+
+            if !used_identities.insert(get_voter_identity(row.name.as_str(), row.class.as_str(), &campus)) {
+                  import_errors.push(VoterImportRowErrorType {
+                        line,
+                        error: VoterImportErrorType::Duplicate,
+                        message: format!("{} of {} already exists", row.name, row.class)
+                  });
+                  continue;
+            }
+
+            import_rows.push((row, campus));
+      }
+
+      if query.dry_run || !import_errors.is_empty() {
+            // A dry run reports how many voters would be imported, nothing is imported when any row has a problem
+            let response = VoterImportResponseType {
+                  dry_run: query.dry_run,
+                  imported: if import_errors.is_empty() { import_rows.len() } else { 0 },
+                  errors: import_errors
+            };
+
+            if response.errors.is_empty() {
+                  return Ok(HttpResponse::Ok().json(response));
+            }
+            return Ok(HttpResponse::BadRequest().json(response));
+      }
+
+      // Import every voter in a single transaction
+      let new_voters: Vec<Voter> = import_rows
+            .into_iter()
+            .map(|(row, campus)| Voter {
+                  voter_id: row.student_number.unwrap_or_else(|| Uuid::new_v4().to_string()),
+                  token: String::new(),
+                  name: row.name,
+                  class: row.class,
+                  campus,
+                  deactivated: false
+            })
+            .collect();
+      let imported: usize = new_voters.len();
+
+      match insert_voters_with_new_tokens(&redis_pool, new_voters).await {
+            Ok(_) => {
+                  log_something("PostVotersImport", format!("{} has imported {} voters", admin_data.admin_id, imported).as_str());
+            },
+            Err(err) => {
+                  log_error("PostVotersImport", format!("There's an error when trying to import voters. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().json(VoterImportResponseType {
+            dry_run: false,
+            imported,
+            errors: Vec::new()
+      }))
+}
+
+#[post("/admin/voters/{voter_id}")]
+pub async fn update(path: web::Path<String>, body: web::Json<VoterBodyRequestType>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let target_voter_id: String = path.into_inner();
+      let voter_data: Voter = match get_voter(target_voter_id.as_str()).await {
+            Some(data) => data,
+            None => {
+                  return Err(AppError::NotFound);
+            }
+      };
+
+      let body = body.into_inner();
+      validate_voter_body(&body)?;
+
+      // The student number identifies the voter and can't be changed
+      if body.voter_id.is_some_and(|voter_id| voter_id != target_voter_id) {
+            return Err(AppError::Validation(String::from("The student number of a voter can't be changed.")));
+      }
+
+      let update_result = update_voter(Voter {
+            name: body.name,
+            class: body.class,
+            campus: body.campus,
+            ..voter_data
+      }).await;
+
+      match update_result {
+            Ok(_) => {
+                  log_something("PostVoter", format!("{} has updated the voter {}", admin_data.admin_id, target_voter_id).as_str());
+            },
+            Err(err) => {
+                  log_error("PostVoter", format!("There's an error when trying to update voter. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
+
+#[delete("/admin/voters/{voter_id}")]
+pub async fn delete(path: web::Path<String>, admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      let admin_data: Admin = admin.require_role(AdminRole::Superadmin)?;
+
+      let target_voter_id: String = path.into_inner();
+      if get_voter(target_voter_id.as_str()).await.is_none() {
+            return Err(AppError::NotFound);
+      }
+
+      // Keep the record so the vote of the voter still counts
+      match deactivate_voter(target_voter_id.as_str()).await {
+            Ok(_) => {
+                  log_something("DeleteVoter", format!("{} has deactivated the voter {}", admin_data.admin_id, target_voter_id).as_str());
+            },
+            Err(err) => {
+                  log_error("DeleteVoter", format!("There's an error when trying to deactivate voter. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      }
+
+      Ok(HttpResponse::Ok().finish())
+}
@@ -1,27 +1,20 @@
 use std::{collections::HashMap, sync::Arc};
 
-use actix_web::{HttpRequest, HttpResponse, get};
+use actix_web::{HttpResponse, get};
 use tokio::sync::RwLock;
 
-use crate::{data::vote::get_votes_count, db::Campus, util::{log_error, verify_admin_token}};
+use crate::{auth::AuthenticatedAdmin, data::vote::{get_votes_count, is_secret_ballot}, db::{AdminRole, Campus}, error::{AppError, AppResult}, util::log_error};
 
 
 #[get("/admin/votes")]
-pub async fn get(req: HttpRequest) -> HttpResponse {
-      // Get the admin token from request cookies
-      let cookie_admin_token = req.cookie("admin_session_token");
-      let cookie_admin_token = match cookie_admin_token {
-          Some(data) => data.value().to_string(),
-          None => {
-              return HttpResponse::Unauthorized().finish();
-          }
-      };
-
-      // Verify the admin token
-      match verify_admin_token(cookie_admin_token.as_str()).await {
-            Ok(_) => (),
-            Err(response) => return response
-      };
+pub async fn get(admin: AuthenticatedAdmin) -> AppResult<HttpResponse> {
+      // Verify the admin role
+      admin.require_role(AdminRole::Superadmin)?;
+
+      // Who voted for whom isn't recorded in secret ballot mode
+      if is_secret_ballot() {
+            return Err(AppError::NotFound);
+      }
 
       // Get the static votes data
       let mut result: HashMap<Campus, HashMap<String, String>> = HashMap::new();
@@ -34,16 +27,16 @@ pub async fn get(req: HttpRequest) -> HttpResponse {
                   Some(data) => data,
                   None => {
                         log_error("PostVote", "The static votes count hasn't initialized yet.");
-                        return HttpResponse::InternalServerError().finish();
+                        return Err(AppError::Internal(String::from("The static votes count hasn't initialized yet.")));
                   }
             };
             let locked_static_votes_data = static_votes_data.read().await;
 
-            result.insert(votes_data.0.clone(), locked_static_votes_data.clone());
+            result.insert(*votes_data.0, locked_static_votes_data.clone());
       }
 
 
 
-      HttpResponse::Ok()
-            .json(result)
+      Ok(HttpResponse::Ok()
+            .json(result))
 }
@@ -1,34 +1,13 @@
 use std::collections::HashMap;
 
-use actix_web::{HttpRequest, HttpResponse, get, web};
-use deadpool_redis::Pool as RedisPool;
+use actix_web::{HttpResponse, get};
 use strum::IntoEnumIterator;
 
-use crate::{data::candidate::get_candidates_data, db::{Campus, Candidate}, util::{log_error, verify_admin_token, verify_voter_token}};
+use crate::{auth::VoterOrAdmin, data::candidate::get_candidates_data, db::{Campus, Candidate}, error::{AppError, AppResult}, util::log_error};
 
 
 #[get("/candidate")]
-pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResponse {
-      // Get admin or voter token from request cookie
-      let admin_or_voter_token = req.cookie("admin_token").or(req.cookie("voter_token"));
-      let admin_or_voter_token: String = match admin_or_voter_token {
-            Some(token) => token.value().to_string(),
-            None => {
-                  return HttpResponse::Unauthorized().finish();
-            }
-      };
-
-
-      // Verify admin or voter token
-      let is_verified: bool = verify_admin_token(admin_or_voter_token.as_str()).await.map(|_| true)
-                  .unwrap_or(verify_voter_token(admin_or_voter_token.as_str(), &redis_pool).await.map(|_| true)
-                  .unwrap_or(false));
-
-      if !is_verified {
-            return HttpResponse::Unauthorized().finish();
-      }
-
-
+pub async fn get(_voter_or_admin: VoterOrAdmin) -> AppResult<HttpResponse> {
       // Get the candidate data
       let mut result_candidate_data: HashMap<Campus, Vec<Candidate>> = HashMap::new();
       let static_candidate_data = get_candidates_data().await;
@@ -42,14 +21,14 @@ pub async fn get(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResp
                   Some(data) => data,
                   None => {
                         log_error("GetCandidate", "All of the campus is not initialized");
-                        return HttpResponse::InternalServerError().finish();
+                        return Err(AppError::Internal(String::from("All of the campus is not initialized")));
                   }
             };
 
-            result_candidate_data_by_campus.push(candidate_data.clone());
+            result_candidate_data_by_campus.push(candidate_data);
       }
 
 
       // Return the candidates data
-      HttpResponse::Ok().json(result_candidate_data)
+      Ok(HttpResponse::Ok().json(result_candidate_data))
 }
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 mod candidate;
 
 pub use self::candidate::get as candidate_get_api;
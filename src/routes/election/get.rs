@@ -0,0 +1,35 @@
+use std::collections::HashMap;
+
+use actix_web::{HttpResponse, get};
+use serde::Serialize;
+
+use crate::{data::election::get_elections_data, db::{Campus, ElectionState}, error::AppResult, util::get_timestamp};
+
+#[derive(Serialize)]
+struct ElectionResponseType {
+      state: ElectionState,
+      starts_at: Option<i64>,
+      ends_at: Option<i64>,
+      server_time: i64
+}
+
+
+#[get("/election")]
+pub async fn get() -> AppResult<HttpResponse> {
+      let current_timestamp: i64 = get_timestamp();
+
+      // Map the election of each campus with the schedule applied
+      let elections_data = get_elections_data();
+      let locked_elections_data = elections_data.read().await;
+      let result: HashMap<Campus, ElectionResponseType> = locked_elections_data
+            .iter()
+            .map(|(campus, election)| (*campus, ElectionResponseType {
+                  state: election.get_state_at(current_timestamp),
+                  starts_at: election.starts_at,
+                  ends_at: election.ends_at,
+                  server_time: current_timestamp
+            }))
+            .collect();
+
+      Ok(HttpResponse::Ok().json(result))
+}
@@ -0,0 +1,14 @@
+use actix_web::{HttpResponse, get};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct HealthzResponseType {
+      alive: bool
+}
+
+
+/// Only tells that the process answers, the dependencies are checked by `/readyz`.
+#[get("/healthz")]
+pub async fn get() -> HttpResponse {
+      HttpResponse::Ok().json(HealthzResponseType { alive: true })
+}
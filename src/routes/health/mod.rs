@@ -0,0 +1,5 @@
+mod healthz;
+mod readyz;
+
+pub use self::healthz::get as healthz_get_api;
+pub use self::readyz::get as readyz_get_api;
@@ -0,0 +1,78 @@
+use std::{collections::HashMap, future::Future, time::{Duration, Instant}};
+
+use actix_web::{HttpResponse, get, web};
+use deadpool_redis::Pool as RedisPool;
+use serde::Serialize;
+
+use crate::{
+      data::{cache_status::{CacheStatusType, get_cache_status, is_caches_loaded}, live_status::{LiveQueryStatusType, get_live_query_status, is_live_queries_connected}},
+      db::ping_db,
+      error::AppResult,
+      rdb::ping_redis,
+      util::log_error
+};
+
+// A dependency slower than this is reported as down
+static DEPENDENCY_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+struct DependencyStatusType {
+      ok: bool,
+      latency_ms: u128,
+      // The details are only logged, the probe may be reachable by anyone
+      error: Option<String>
+}
+
+#[derive(Serialize)]
+struct ReadyzResponseType {
+      ready: bool,
+      database: DependencyStatusType,
+      redis: DependencyStatusType,
+      live_queries: HashMap<&'static str, LiveQueryStatusType>,
+      caches: HashMap<&'static str, CacheStatusType>
+}
+
+async fn check_dependency(dependency: &str, check: impl Future<Output = AppResult<()>>) -> DependencyStatusType {
+      let started_at: Instant = Instant::now();
+      let result: Result<(), String> = match tokio::time::timeout(DEPENDENCY_CHECK_TIMEOUT, check).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(err)) => {
+                  log_error("Readyz", format!("There's an error when trying to ping {}. Error: {}", dependency, err).as_str());
+                  Err(String::from("The ping failed."))
+            },
+            Err(_) => Err(format!("No answer within {} seconds.", DEPENDENCY_CHECK_TIMEOUT.as_secs()))
+      };
+
+      DependencyStatusType {
+            ok: result.is_ok(),
+            latency_ms: started_at.elapsed().as_millis(),
+            error: result.err()
+      }
+}
+
+
+#[get("/readyz")]
+pub async fn get(redis_pool: web::Data<RedisPool>) -> HttpResponse {
+      let (database, redis) = tokio::join!(
+            check_dependency("SurrealDB", ping_db()),
+            check_dependency("Redis", ping_redis(&redis_pool))
+      );
+
+      // Stale caches are still served, so they only have to be loaded once
+      let live_queries_connected: bool = is_live_queries_connected().await;
+      let caches_loaded: bool = is_caches_loaded().await;
+
+      let response = ReadyzResponseType {
+            ready: database.ok && redis.ok && live_queries_connected && caches_loaded,
+            database,
+            redis,
+            live_queries: get_live_query_status().await,
+            caches: get_cache_status().await
+      };
+
+      if !response.ready {
+            return HttpResponse::ServiceUnavailable().json(response);
+      }
+
+      HttpResponse::Ok().json(response)
+}
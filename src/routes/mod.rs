@@ -2,3 +2,6 @@ pub mod voter;
 pub mod admin;
 pub mod ws;
 pub mod candidate;
+pub mod election;
+pub mod receipt;
+pub mod health;
@@ -0,0 +1,41 @@
+use actix_web::{HttpResponse, get, web};
+use serde::Serialize;
+
+use crate::{data::vote::is_secret_ballot, db::get_candidate_by_ballot_id, error::{AppError, AppResult}, util::{log_error, parse_receipt, verify_receipt}};
+
+#[derive(Serialize)]
+struct ReceiptResponseType {
+      counted: bool
+}
+
+
+#[get("/receipt/{code}")]
+pub async fn get(path: web::Path<String>) -> AppResult<HttpResponse> {
+      // Parse the receipt code
+      let receipt: String = path.into_inner();
+      let (ballot_id, mac) = match parse_receipt(receipt.as_str()) {
+            Some(data) => data,
+            None => {
+                  return Err(AppError::Validation(String::from("The receipt code is malformed.")));
+            }
+      };
+
+      // Get the ballot, it's gone if the vote was reset
+      let candidate_id: Option<String> = match get_candidate_by_ballot_id(ballot_id.clone(), is_secret_ballot()).await {
+            Ok(data) => data,
+            Err(err) => {
+                  log_error("GetReceipt", format!("There's an error when trying to get the ballot. Error: {}", err).as_str());
+                  return Err(err);
+            }
+      };
+
+      // Verify the receipt against the stored ballot without revealing the choice
+      let is_counted: bool = candidate_id
+            .is_some_and(|candidate_id| verify_receipt(ballot_id.as_str(), candidate_id.as_str(), &mac));
+
+      if !is_counted {
+            return Err(AppError::NotFound);
+      }
+
+      Ok(HttpResponse::Ok().json(ReceiptResponseType { counted: true }))
+}
@@ -0,0 +1,3 @@
+mod get;
+
+pub use self::get::get as receipt_get_api;
@@ -1,29 +1,10 @@
-use actix_web::{HttpRequest, HttpResponse, post, web};
-use deadpool_redis::{self, Pool as RedisPool};
+use actix_web::{HttpResponse, post};
 
-use crate::{util::verify_voter_token};
+use crate::auth::AuthenticatedVoter;
 
 
 #[post("/voter/check")]
-pub async fn post(req: HttpRequest, redis_pool: web::Data<RedisPool>) -> HttpResponse {
-      // Get the user token from request cookies
-      let cookie_user_token = req.cookie("voter_token");
-      let cookie_user_token = match cookie_user_token {
-          Some(data) => data.value().to_string(),
-          None => {
-              return HttpResponse::Unauthorized().finish();
-          }
-      };
-
-
-      // Verify the token from checking into the Redis database
-      let _ = match verify_voter_token(cookie_user_token.as_str(), &redis_pool).await {
-            Ok(_) => (),
-            Err(response) => {
-                  return response;
-            }
-      };
-
-
+pub async fn post(_voter: AuthenticatedVoter) -> HttpResponse {
+      // The voter token is verified by the extractor
       HttpResponse::Ok().finish()
 }
@@ -1,81 +1,112 @@
-use crate::{data::voter::get_voters_data, util::log_error};
-use actix_web::{HttpResponse, cookie::Cookie, post, web};
-use deadpool_redis::{Pool as RedisPool, PoolError};
-use redis::{AsyncCommands, RedisError};
+use crate::{
+    auth::{VOTER_TOKEN_COOKIE, build_cookie},
+    data::voter::{get_voter_ids_by_name, get_voters_data},
+    db::Voter,
+    error::{AppError, AppResult},
+    middleware::login_rate_limit,
+    rdb::{LoginLimitScope, clear_login_failures_redis, get_login_lockout_redis, get_voter_reset_token_redis, register_login_failure_redis},
+    util::verify_token_checksum,
+};
+use actix_web::{HttpResponse, middleware::from_fn, post, web};
+use deadpool_redis::Pool as RedisPool;
 use serde::Deserialize;
 
+/// The voter is looked up by the student number, or by the name when it's not given.
 #[derive(Deserialize)]
 struct UserData {
-    fullname: String,
+    voter_id: Option<String>,
+    fullname: Option<String>,
     token: String,
 }
 
-#[post("/voter/get")]
-pub async fn post(redis_pool: web::Data<RedisPool>, data: web::Json<UserData>) -> HttpResponse {
+#[post("/voter/get", wrap = "from_fn(login_rate_limit)")]
+pub async fn post(redis_pool: web::Data<RedisPool>, data: web::Json<UserData>) -> AppResult<HttpResponse> {
     // Get the targetted user data token
     let data = data.into_inner();
-    let target_user_fullname = data.fullname;
     let target_user_token = data.token;
-
-    // Check in the users hashmap
-    let static_voters_data = get_voters_data();
-    let locked_static_voters_data = static_voters_data.read().await;
-    let static_voter_data = locked_static_voters_data.get(&target_user_fullname);
-    let static_voter_data = match static_voter_data {
+    let target_user_lookup: String = match data.voter_id.clone().or(data.fullname.clone()) {
         Some(data) => data,
         None => {
-            return HttpResponse::NotFound().finish();
+            return Err(AppError::Validation(String::from("Either the student number or the name is required.")));
         }
     };
 
+    // Reject mistyped tokens before looking up the voter
+    if !verify_token_checksum(target_user_token.as_str()) {
+        return Err(AppError::Validation(String::from("The token is mistyped.")));
+    }
+
+    // Check in the users hashmap, several voters can share the same name
+    let target_user_ids: Vec<String> = match (data.voter_id, data.fullname) {
+        (Some(voter_id), _) => vec![voter_id],
+        (None, Some(fullname)) => get_voter_ids_by_name().await.remove(&fullname).unwrap_or_default(),
+        (None, None) => Vec::new(),
+    };
+
+    let static_voters_data = get_voters_data();
+    let locked_static_voters_data = static_voters_data.read().await;
+    let static_voters_data_maybe: Vec<&Voter> = target_user_ids
+        .iter()
+        .filter_map(|voter_id| locked_static_voters_data.get(voter_id))
+        .collect();
+
+    // Unknown voters are locked out by what was typed, so guessing them is limited too
+    if static_voters_data_maybe.is_empty() {
+        if let Some(remaining_lockout) = get_login_lockout_redis(&redis_pool, LoginLimitScope::Voter, target_user_lookup.as_str()).await? {
+            return Err(AppError::TooManyRequests(remaining_lockout));
+        }
+
+        register_login_failure_redis(&redis_pool, LoginLimitScope::Voter, target_user_lookup.as_str()).await?;
+        return Err(AppError::NotFound);
+    }
+
+    // Check if the voter is locked out from too many failed attempts, whether they're looked up by the student number or the name
+    for static_voter_data_maybe in &static_voters_data_maybe {
+        if let Some(remaining_lockout) = get_login_lockout_redis(&redis_pool, LoginLimitScope::Voter, static_voter_data_maybe.voter_id.as_str()).await? {
+            return Err(AppError::TooManyRequests(remaining_lockout));
+        }
+    }
+
     // Check in the Redis if the token is resetted
-    let redis_connection_result: Result<deadpool_redis::Connection, PoolError> =
-        redis_pool.get().await;
-    let mut redis_connection: deadpool_redis::Connection = match redis_connection_result {
-        Ok(connection) => connection,
-        Err(err) => {
-            log_error(
-                "VoterGet",
-                format!(
-                    "There's an error when trying to get admin redis pool. Error: {}",
-                    err.to_string()
-                )
-                .as_str(),
-            );
-            return HttpResponse::InternalServerError().finish();
+    let mut static_voter_data: Option<&Voter> = None;
+    for static_voter_data_maybe in &static_voters_data_maybe {
+        let redis_user_token_maybe: Option<String> =
+            get_voter_reset_token_redis(&redis_pool, static_voter_data_maybe.voter_id.as_str()).await?;
+
+        // Check for token in Redis
+        let is_token_valid: bool = match &redis_user_token_maybe {
+            Some(redis_user_token) => &target_user_token == redis_user_token,
+            // If there's no targetted user token in redis check with the default data user token
+            None => target_user_token == static_voter_data_maybe.token,
+        };
+
+        if is_token_valid {
+            static_voter_data = Some(*static_voter_data_maybe);
+            break;
         }
-    };
+    }
 
-    let redis_user_token_result: Result<Option<String>, RedisError> = redis_connection
-        .hget("voter_token_reset", target_user_fullname)
-        .await;
-    let redis_user_token_maybe: Option<String> = match redis_user_token_result {
-        Ok(data) => data,
-        Err(err) => {
-            log_error("UserGet", err.to_string().as_str());
-            return HttpResponse::InternalServerError().finish();
+    let static_voter_data: &Voter = match static_voter_data {
+        Some(data) => data,
+        None => {
+            for static_voter_data_maybe in &static_voters_data_maybe {
+                register_login_failure_redis(&redis_pool, LoginLimitScope::Voter, static_voter_data_maybe.voter_id.as_str()).await?;
+            }
+            return Err(AppError::Unauthorized);
         }
     };
 
-    // Check for token in Redis
-    if let Some(redis_user_token) = &redis_user_token_maybe
-        && &target_user_token != redis_user_token
-    {
-        return HttpResponse::Unauthorized().finish();
-    }
+    clear_login_failures_redis(&redis_pool, LoginLimitScope::Voter, static_voter_data.voter_id.as_str()).await?;
 
-    // If there's no targetted user token in redis check with the default data user token
-    if redis_user_token_maybe.is_none() && target_user_token != static_voter_data.token {
-        return HttpResponse::Unauthorized().finish();
+    // Deactivated voters are removed from the roster
+    if static_voter_data.deactivated {
+        return Err(AppError::Forbidden);
     }
 
     // Create response object and add cookie
-    let cookie_user_token = Cookie::build("voter_token", target_user_token)
-        .path("/")
-        .secure(true)
-        .http_only(true)
+    let cookie_user_token = build_cookie(VOTER_TOKEN_COOKIE, target_user_token)
         .finish();
 
     // Return the response
-    HttpResponse::Ok().cookie(cookie_user_token).json(static_voter_data)
+    Ok(HttpResponse::Ok().cookie(cookie_user_token).json(static_voter_data))
 }
@@ -1,14 +1,13 @@
-use actix_web::{HttpResponse, cookie::Cookie, post};
+use actix_web::{HttpResponse, post};
 use time::Duration;
 
+use crate::{auth::{VOTER_TOKEN_COOKIE, build_cookie}, error::AppResult};
+
 #[post("/voter/logout")]
-pub async fn post() -> HttpResponse {
-      let clear_cookie = Cookie::build("voter_token", "")
-            .path("/")
-            .secure(true)
-            .http_only(true)
+pub async fn post() -> AppResult<HttpResponse> {
+      let clear_cookie = build_cookie(VOTER_TOKEN_COOKIE, "")
             .max_age(Duration::seconds(0))
             .finish();
 
-      HttpResponse::Ok().cookie(clear_cookie).finish()
+      Ok(HttpResponse::Ok().cookie(clear_cookie).finish())
 }
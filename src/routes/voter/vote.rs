@@ -1,53 +1,47 @@
 use std::{collections::HashMap, sync::Arc};
 
-use actix_web::{HttpRequest, HttpResponse, post, web};
-use deadpool_redis::{self, Pool as RedisPool};
-use serde::Deserialize;
+use actix_web::{HttpResponse, post, web};
+use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 use crate::{
-    data::{candidate::get_candidates_data, vote::get_votes_count},
-    db::{Campus, Voter, insert_vote},
-    util::{log_error, log_something, verify_voter_token},
+    auth::AuthenticatedVoter,
+    data::{candidate::get_candidates_data, election::get_election_state, vote::{get_votes_count, is_secret_ballot, record_vote}},
+    db::{Campus, ElectionState, Vote, Voter, insert_secret_vote, insert_vote},
+    error::{AppError, AppResult},
+    util::{generate_receipt, log_error, log_something},
 };
 
 #[derive(Deserialize)]
 struct VoteBodyRequest {
-    candidate_fullname: String,
+    candidate_id: String,
+}
+
+#[derive(Serialize)]
+struct VoteBodyResponse {
+    receipt: Option<String>,
 }
 
 #[post("/voter/vote")]
 pub async fn post(
     body: web::Json<VoteBodyRequest>,
-    req: HttpRequest,
-    redis_pool: web::Data<RedisPool>,
-) -> HttpResponse {
-    // Get the user token from request cookies
-    let cookie_user_token = req.cookie("voter_token");
-    let cookie_user_token = match cookie_user_token {
-        Some(data) => data.value().to_string(),
-        None => {
-            return HttpResponse::Unauthorized().finish();
-        }
-    };
-
+    voter: AuthenticatedVoter,
+) -> AppResult<HttpResponse> {
+    let target_voter_data: Voter = voter.0;
 
-    // Verify the token from checking into the Redis database
-    let target_voter_data: Voter = match verify_voter_token(cookie_user_token.as_str(), &redis_pool).await {
-          Ok(voter) => voter,
-          Err(response) => {
-                return response;
-          }
-    };
+    // Verify the election of the voter campus is open
+    if get_election_state(&target_voter_data.campus).await != ElectionState::Open {
+        return Err(AppError::ElectionClosed);
+    }
 
     // Get the candidate info from request data
     let request_body = body.into_inner();
-    let target_candidate_fullname: String = request_body.candidate_fullname;
+    let target_candidate_id: String = request_body.candidate_id;
     let target_voter_fullname: &String = &target_voter_data.name;
 
-    // Verify candidate name
+    // Verify candidate ID
     let candidates_data = get_candidates_data().await;
-    let target_candidate_data = candidates_data.iter().find(|data| data.president == target_candidate_fullname);
+    let target_candidate_data = candidates_data.iter().find(|data| data.candidate_id == target_candidate_id);
     let target_candidate_data = match target_candidate_data {
           Some(data) => data,
           None => {
@@ -55,16 +49,16 @@ pub async fn post(
                   "PostVote",
                   format!(
                   "{} has votes {} that is currently not registered",
-                  target_voter_fullname, target_candidate_fullname
+                  target_voter_fullname, target_candidate_id
                   )
                   .as_str(),
             );
-            return HttpResponse::BadRequest().finish();
+            return Err(AppError::Validation(String::from("The candidate doesn't exist.")));
           }
     };
 
     if target_candidate_data.campus != target_voter_data.campus {
-          return HttpResponse::Unauthorized().finish();
+          return Err(AppError::Unauthorized);
     }
 
     // Get the static vote
@@ -74,48 +68,76 @@ pub async fn post(
           Some(data) => data,
           None => {
                 log_error("PostVote", "The static votes count hasn't initialized yet.");
-                return HttpResponse::InternalServerError().finish();
+                return Err(AppError::Internal(String::from("The static votes count hasn't initialized yet.")));
           }
     };
 
-    let mut locked_static_votes_data = static_votes_data.write().await;
-
-    // Verify that the haven't voted yet.
-    if locked_static_votes_data.contains_key(target_voter_fullname) {
-          return HttpResponse::Conflict().finish();
+    // Fast path, the database unique index is the real guard against double votes.
+    if static_votes_data.read().await.contains_key(&target_voter_data.voter_id) {
+          return Err(AppError::Conflict(String::from("The voter has already voted.")));
     }
 
 
     // Create vote record into the SurrealDB
-    let vote_record = insert_vote(
-        target_voter_fullname.clone(),
-        target_candidate_fullname.clone(),
-        target_candidate_data.campus
-    )
-    .await;
+    let vote_record = if is_secret_ballot() {
+        insert_secret_vote(
+            target_voter_data.voter_id.clone(),
+            target_voter_fullname.clone(),
+            target_candidate_id.clone(),
+            target_candidate_data.campus
+        )
+        .await
+        .map(|ballot| ballot.ballot_id)
+    } else {
+        insert_vote(
+            target_voter_data.voter_id.clone(),
+            target_voter_fullname.clone(),
+            target_candidate_id.clone(),
+            target_candidate_data.campus
+        )
+        .await
+        .map(|vote| vote.ballot_id)
+    };
 
-    match vote_record {
-        Ok(_) => {
+    let ballot_id: String = match vote_record {
+        Ok(ballot_id) if is_secret_ballot() => {
+            log_something("PostVote", format!("{} has successfully votes", target_voter_fullname).as_str());
+            ballot_id
+        }
+        Ok(ballot_id) => {
             log_something(
                 "PostVote",
                 format!(
                     "{} has successfully votes {}",
-                    target_voter_fullname, target_candidate_fullname
+                    target_voter_fullname, target_candidate_data.president
                 )
                 .as_str(),
             );
+            ballot_id
+        }
+        Err(err @ AppError::Conflict(_)) => {
+            return Err(err);
         }
         Err(err) => {
-            log_error("PostVote", format!("There's an error when trying to update vote record into the database. Error: {}", err.to_string()).as_str());
-            return HttpResponse::InternalServerError().finish();
+            log_error("PostVote", format!("There's an error when trying to update vote record into the database. Error: {}", err).as_str());
+            return Err(err);
         }
-    }
+    };
 
+    let receipt: Option<String> = generate_receipt(ballot_id.as_str(), target_candidate_id.as_str());
 
-    // Put the vote data inside the static data
-    locked_static_votes_data.insert(target_voter_fullname.clone(), target_candidate_fullname);
+
+    // Put the vote data inside the static data once the database confirmed it, the choice stays unknown in secret ballot mode
+    record_vote(Vote {
+        voter_id: target_voter_data.voter_id.clone(),
+        voter_name: target_voter_fullname.clone(),
+        candidate_id: if is_secret_ballot() { String::new() } else { target_candidate_id },
+        campus: target_candidate_data.campus,
+        ballot_id: if is_secret_ballot() { String::new() } else { ballot_id },
+    })
+    .await;
 
 
-    // Return OK
-    HttpResponse::Ok().finish()
+    // Return OK with the receipt
+    Ok(HttpResponse::Ok().json(VoteBodyResponse { receipt }))
 }
@@ -0,0 +1,19 @@
+use actix_ws::handle;
+use actix_web::{HttpRequest, HttpResponse, get, web};
+
+use crate::{
+      auth::AuthenticatedAdmin,
+      data::live_clients::{get_admin_live_clients, serve_live_client},
+      db::AdminRole,
+};
+
+#[get("/ws/admin/votes")]
+pub async fn ws_handler(req: HttpRequest, body: web::Payload, admin: AuthenticatedAdmin) -> actix_web::Result<HttpResponse> {
+      // Only superadmins can see who voted for whom
+      admin.require_role(AdminRole::Superadmin)?;
+
+      let (response, session, msg_stream) = handle(&req, body)?;
+      serve_live_client(get_admin_live_clients(), session, msg_stream).await;
+
+      Ok(response)
+}
@@ -1,38 +1,43 @@
-use std::{collections::HashMap, sync::Arc};
-
 use actix_ws::handle;
-use futures_util::StreamExt;
 use actix_web::{HttpRequest, HttpResponse, get, web};
-use surrealdb::Uuid;
-use tokio::sync::RwLock;
+use strum::IntoEnumIterator;
 
-use crate::{data::live_clients::get_live_clients};
+use crate::{
+      data::{
+            election::{get_election_message, get_elections_data, is_results_embargoed},
+            live_clients::{get_live_clients, serve_live_client},
+            vote::{get_tally_messages, get_turnout_messages},
+      },
+      db::{Campus, Election},
+};
 
 #[get("/ws/votes")]
 pub async fn ws_handler(req: HttpRequest, body: web::Payload) -> actix_web::Result<HttpResponse> {
-      let (response, mut session, mut msg_stream) = handle(&req, body)?;
+      let (response, mut session, msg_stream) = handle(&req, body)?;
 
-      let client_id: String = Uuid::new_v4().to_string();
+      // Send the current election state so the client can show it right away
       {
-            let live_clients: Arc<RwLock<HashMap<String, actix_ws::Session>>> = get_live_clients();
-            let mut locked_write_live_clients = live_clients.write().await;
-            locked_write_live_clients.insert(client_id.clone(), session.clone());
+            let elections_data = get_elections_data();
+            let elections: Vec<Election> = elections_data.read().await.values().cloned().collect();
+            for election in elections.iter() {
+                  let _ = session.text(get_election_message(election)).await;
+            }
       }
 
-      actix_web::rt::spawn(async move {
-            while let Some(Ok(msg)) = msg_stream.next().await {
-                  match msg {
-                        actix_ws::Message::Ping(bytes) => {
-                              let _ = session.pong(&bytes).await;
-                        }
-                        _ => ()
-                  }
+      // Send the current counts, only the turnout while the results are embargoed
+      for campus in Campus::iter() {
+            let mut messages: Vec<String> = Vec::new();
+            if !is_results_embargoed(&campus).await {
+                  messages.extend(get_tally_messages(&campus).await);
             }
+            messages.extend(get_turnout_messages(&campus).await);
+
+            for message in messages {
+                  let _ = session.text(message).await;
+            }
+      }
 
-            let live_clients: Arc<RwLock<HashMap<String, actix_ws::Session>>> = get_live_clients();
-            let mut locked_write_live_clients = live_clients.write().await;
-            locked_write_live_clients.remove(&client_id);
-      });
+      serve_live_client(get_live_clients(), session, msg_stream).await;
 
       Ok(response)
 }
@@ -1,3 +1,5 @@
 mod live_votes;
+mod admin_live_votes;
 
 pub use self::live_votes::ws_handler as live_votes_data;
+pub use self::admin_live_votes::ws_handler as admin_live_votes_data;
@@ -1,28 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use actix_web::HttpResponse;
-use deadpool_redis::{Pool as RedisPool, PoolError};
-use redis::{AsyncCommands, RedisError};
-use time::{OffsetDateTime, macros::{format_description, offset}};
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::{SaltString, rand_core::OsRng}};
+use deadpool_redis::Pool as RedisPool;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use time::{OffsetDateTime, macros::format_description};
 use rand::Rng;
+use subtle::ConstantTimeEq;
 
-use crate::{data::{admin::get_all_admin_data, voter::get_voters_data}, db::{Admin, Voter}};
+use crate::{config::{TokenConfig, get_config, get_timezone}, data::{admin::get_all_admin_data, voter::{get_voter_by_token, get_voters_data}}, db::{Admin, Voter, insert_voters, is_voter_token_conflict}, error::{AppError, AppResult}, rdb::{RedisAdminSessionType, RedisVoterType, get_admin_session_redis, get_voter_id_by_reset_token_redis, get_voter_reset_token_redis, get_voters_data_redis}};
 
 static DATETIME_FMT: &[time::format_description::FormatItem<'static>] = format_description!("[hour]:[minute]:[second]");
 
 pub fn get_time() -> String {
       let utc = OffsetDateTime::now_utc();
-      let result_time = utc.to_offset(offset!(+7)).format(DATETIME_FMT);
+      let result_time = utc.to_offset(get_timezone()).format(DATETIME_FMT);
 
       match result_time {
             Ok(data) => data.to_string(),
             Err(err) => {
-                  log_error("Util", format!("There's an error when get the current time. Error: {}", err.to_string()).as_str());
+                  log_error("Util", format!("There's an error when get the current time. Error: {}", err).as_str());
                   String::from("--:--:--")
             }
       }
 }
 
+pub fn get_timestamp() -> i64 {
+      OffsetDateTime::now_utc().unix_timestamp()
+}
+
 pub fn log_something(scope_title: &str, message: &str) {
       println!("[{}] [{}] {}", get_time(), scope_title, message);
 }
@@ -31,130 +37,264 @@ pub fn log_error(scope_title: &str, message: &str) {
       println!("[{}] [ERROR] [{}] {}", get_time(), scope_title, message);
 }
 
-static TOKEN_LENGTH: usize = 5;
+static TOKEN_GENERATION_ATTEMPTS: usize = 32;
+// Another instance may give the same token to a voter between the generation and the insert
+static VOTER_INSERT_ATTEMPTS: usize = 3;
+
+/// Luhn mod N check character of the token.
+fn get_token_check_character(token: &str, alphabet: &[char]) -> Option<char> {
+      let alphabet_length: usize = alphabet.len();
+      let mut factor: usize = 2;
+      let mut sum: usize = 0;
+
+      for character in token.chars().rev() {
+            let code_point: usize = alphabet.iter().position(|data| *data == character)?;
+            let addend: usize = factor * code_point;
+            sum += addend / alphabet_length + addend % alphabet_length;
+            factor = if factor == 2 { 1 } else { 2 };
+      }
+
+      alphabet.get((alphabet_length - sum % alphabet_length) % alphabet_length).copied()
+}
 
 pub fn generate_token() -> String {
-      let mut result: String = String::new();
+      let token_config: &TokenConfig = &get_config().token;
       let mut rng = rand::rng();
 
-      // Iterate for each characters
-      // for i in 65..(65+52) {
-      //       if i > 25 { i += 6; }
-
-      // }
-      for i in 0..=(TOKEN_LENGTH) {
-            let mut random_index = rng.random_range(65..(65+52));
-            if random_index > 90 { random_index += 6; }
+      let mut result: String = (0..token_config.voter_length)
+            .map(|_| token_config.voter_alphabet[rng.random_range(0..token_config.voter_alphabet.len())])
+            .collect();
 
-            if let Some(data) = char::from_u32(random_index) {
-                  result.insert(i, data);
-            }
-            else {
-                  result.insert(i, 'A');
-            }
+      if token_config.voter_checksum
+            && let Some(check_character) = get_token_check_character(result.as_str(), &token_config.voter_alphabet)
+      {
+            result.push(check_character);
       }
 
       result
 }
 
-pub async fn verify_voter_token<T: AsRef<str>>(target_user_token: T, redis_pool: &RedisPool) -> Result<Voter, HttpResponse> {
-      let target_user_token: &str = target_user_token.as_ref();
+/// Reject mistyped tokens before looking them up. Always passes when the checksum is disabled.
+/// Only the tokens long enough to carry a check character are checked, the ones issued before the checksum
+/// was enabled don't have it and are looked up as they are.
+pub fn verify_token_checksum<T: AsRef<str>>(token: T) -> bool {
+      let token_config: &TokenConfig = &get_config().token;
+      if !token_config.voter_checksum {
+            return true;
+      }
 
-      let redis_connection_result: Result<deadpool_redis::Connection, PoolError> =
-          redis_pool.get().await;
-      let mut redis_connection: deadpool_redis::Connection = match redis_connection_result {
-          Ok(connection) => connection,
-          Err(err) => {
-              log_error(
-                  "PostReset",
-                  format!(
-                      "There's an error when trying to get admin redis pool. Error: {}",
-                      err.to_string()
-                  )
-                  .as_str(),
-              );
-              return Err(HttpResponse::InternalServerError().finish());
-          }
-      };
+      let token: &str = token.as_ref();
+      if token.chars().count() != token_config.voter_length + 1 {
+            return true;
+      }
 
-      let redis_user_token_result: Result<HashMap<String, String>, RedisError> =
-          redis_connection.hgetall("voter_token_reset").await;
-      let redis_user_tokens: HashMap<String, String> = match redis_user_token_result {
-          Ok(data) => data,
-          Err(err) => {
-              log_error("PostVote", err.to_string().as_str());
-              return Err(HttpResponse::InternalServerError().finish());
-          }
-      };
-      let redis_user_name_by_cookie_token: Option<String> = redis_user_tokens
-          .iter()
-          .find(|(_, v)| v.as_str() == target_user_token)
-          .map(|user_data| user_data.0.clone());
+      let mut token_characters = token.chars();
+      let check_character: Option<char> = token_characters.next_back();
 
+      check_character.is_some()
+            && get_token_check_character(token_characters.as_str(), &token_config.voter_alphabet) == check_character
+}
+
+/// Generate tokens that no other voter has, either in the static data or as a reset token in Redis.
+pub async fn generate_unique_voter_tokens(redis_pool: &RedisPool, count: usize) -> AppResult<Vec<String>> {
+      let redis_voter_tokens: HashMap<String, RedisVoterType> = get_voters_data_redis(redis_pool).await?;
 
-      // Verify the token from checking into the redis database
       let static_voters_data = get_voters_data();
       let locked_static_voters_data = static_voters_data.read().await;
-      let static_voter_data_maybe = locked_static_voters_data
-          .iter()
-          .find(|data| data.1.token == target_user_token);
-      let static_voter_name: Option<String> = match static_voter_data_maybe {
-          Some(data) => Some(data.0.clone()),
-          None => None,
-      };
 
+      let mut used_tokens: HashSet<String> = locked_static_voters_data
+            .values()
+            .map(|data| data.token.clone())
+            .chain(redis_voter_tokens.into_values().map(|data| data.token))
+            .collect();
+
+      let mut result: Vec<String> = Vec::with_capacity(count);
+      for _ in 0..count {
+            let new_token: Option<String> = (0..TOKEN_GENERATION_ATTEMPTS)
+                  .map(|_| generate_token())
+                  .find(|new_token| !used_tokens.contains(new_token));
+
+            match new_token {
+                  Some(new_token) => {
+                        used_tokens.insert(new_token.clone());
+                        result.push(new_token);
+                  },
+                  None => {
+                        log_error("GenerateToken", "Failed to generate a unique voter token, consider increasing the token length.");
+                        return Err(AppError::Internal(String::from("Failed to generate a unique voter token.")));
+                  }
+            }
+      }
+
+      Ok(result)
+}
+
+/// Generate a token that no other voter has, either in the static data or as a reset token in Redis.
+pub async fn generate_unique_voter_token(redis_pool: &RedisPool) -> AppResult<String> {
+      let mut new_tokens: Vec<String> = generate_unique_voter_tokens(redis_pool, 1).await?;
+
+      match new_tokens.pop() {
+            Some(new_token) => Ok(new_token),
+            None => Err(AppError::Internal(String::from("No voter token was generated."))),
+      }
+}
+
+/// Give the voters new tokens and insert them, with other tokens when the database already has one of them.
+pub async fn insert_voters_with_new_tokens(redis_pool: &RedisPool, mut voters: Vec<Voter>) -> AppResult<Vec<Voter>> {
+      let mut attempt: usize = 1;
+      loop {
+            let new_voter_tokens: Vec<String> = generate_unique_voter_tokens(redis_pool, voters.len()).await?;
+            for (voter, new_voter_token) in voters.iter_mut().zip(new_voter_tokens) {
+                  voter.token = new_voter_token;
+            }
+
+            match insert_voters(voters.clone()).await {
+                  Ok(_) => return Ok(voters),
+                  Err(err) if is_voter_token_conflict(&err) && attempt < VOTER_INSERT_ATTEMPTS => {
+                        log_something("InsertVoters", "A new voter token is already used by another voter, generating them again.");
+                        attempt += 1;
+                  },
+                  Err(err) => return Err(err)
+            }
+      }
+}
+
+pub async fn verify_voter_token<T: AsRef<str>>(target_user_token: T, redis_pool: &RedisPool) -> AppResult<Voter> {
+      let target_user_token: &str = target_user_token.as_ref();
+      if !verify_token_checksum(target_user_token) {
+            return Err(AppError::Unauthorized);
+      }
 
       // Verify the token using this step:
-      // 1. Positive if the token is inside Redis
-      // 2. Negative if the token is inside of the voter inside Hashmap and not inside Redis
-      // 3. Negative if the token is inside the Redis
-      let redis_user_token_by_data_user_name = match &static_voter_name {
-          Some(fullname) => redis_user_tokens.get(fullname),
-          None => None,
+      // 1. Positive if the token is the current reset token in Redis
+      // 2. Positive if the token is the initial token of a voter that has no reset token in Redis
+      // 3. Negative otherwise, including the initial token of a voter whose token was reset
+      let target_voter_data: Voter = match get_voter_id_by_reset_token_redis(redis_pool, target_user_token).await? {
+            Some(voter_id) if get_voter_reset_token_redis(redis_pool, voter_id.as_str()).await?.as_deref() == Some(target_user_token) => {
+                  match get_voters_data().read().await.get(&voter_id) {
+                        Some(data) => data.clone(),
+                        None => {
+                              // The voter was removed after their token was reset
+                              log_something("PostVote", "There's a reset token in Redis of a voter that doesn't exist anymore.");
+                              return Err(AppError::Unauthorized);
+                        }
+                  }
+            },
+            _ => {
+                  let static_voter_data: Voter = match get_voter_by_token(target_user_token).await {
+                        Some(data) => data,
+                        None => {
+                              return Err(AppError::Unauthorized);
+                        }
+                  };
+
+                  if get_voter_reset_token_redis(redis_pool, static_voter_data.voter_id.as_str()).await?.is_some() {
+                        return Err(AppError::Unauthorized);
+                  }
+
+                  static_voter_data
+            }
       };
 
-      if redis_user_name_by_cookie_token.is_none()
-          && (redis_user_token_by_data_user_name.is_some() || static_voter_name.is_none())
-      {
-          return Err(HttpResponse::Unauthorized().finish());
+      if target_voter_data.deactivated {
+            return Err(AppError::Unauthorized);
       }
 
-      let target_voter_data: Option<&Voter> = match redis_user_name_by_cookie_token.or(static_voter_name) {
-              Some(data) => locked_static_voters_data.get(&data),
-              None => {
-                    log_error("PostVote", "There's no voter data from either Static data and Redis data but the condition passes!");
-                    return Err(HttpResponse::Unauthorized().finish());
-              }
-      };
-      let target_voter_data: &Voter = match target_voter_data {
+      Ok(target_voter_data)
+}
+
+pub async fn verify_admin_token<T: AsRef<str>>(target_admin_token: T, redis_pool: &RedisPool) -> AppResult<Admin> {
+      // Get the admin session from Redis
+      let target_admin_token: &str = target_admin_token.as_ref();
+      let admin_session: RedisAdminSessionType = match get_admin_session_redis(redis_pool, target_admin_token).await? {
             Some(data) => data,
             None => {
-                  log_error("PostVote", "There's no voter data in static variables but it seems that the data is exists in Redis!");
-                  return Err(HttpResponse::InternalServerError().finish());
+                  return Err(AppError::Unauthorized);
             }
       };
 
-      Ok(target_voter_data.clone())
-}
-
-pub async fn verify_admin_token<T: AsRef<str>>(target_admin_token: T) -> Result<Admin, HttpResponse> {
-      // Get the static admin token
-      let target_admin_token: &str = target_admin_token.as_ref();
+      // Get the static admin data of the session owner
       let static_admin_data = get_all_admin_data();
       let locked_static_admin_data = static_admin_data.read().await;
-      let admin_data: Option<&Admin> = locked_static_admin_data.iter().find(|data| {
-            match &data.1.admin_session_token {
-                  Some(token) => {
-                        token.as_str() == target_admin_token
-                  },
-                  None => {
-                        return false;
-                  }
-            }
-      }).map(|data| data.1);
+      let admin_data: Option<&Admin> = locked_static_admin_data.get(&admin_session.admin_id);
 
       match admin_data {
             Some(data) => Ok(data.clone()),
-            None => Err(HttpResponse::Unauthorized().finish())
+            None => Err(AppError::Unauthorized)
+      }
+}
+
+
+pub enum PasswordCheck {
+      Valid,
+      ValidLegacy,
+      Invalid,
+}
+
+pub fn hash_password<T: AsRef<str>>(password: T) -> Result<String, argon2::password_hash::Error> {
+      let salt = SaltString::generate(&mut OsRng);
+      let password_hash = Argon2::default().hash_password(password.as_ref().as_bytes(), &salt)?;
+
+      Ok(password_hash.to_string())
+}
+
+pub fn verify_password<T: AsRef<str>, U: AsRef<str>>(password: T, stored_password: U) -> PasswordCheck {
+      let password: &str = password.as_ref();
+      let stored_password: &str = stored_password.as_ref();
+
+      // The stored password is a PHC string, verify it with Argon2 (constant time)
+      if let Ok(parsed_hash) = PasswordHash::new(stored_password) {
+            return match Argon2::default().verify_password(password.as_bytes(), &parsed_hash) {
+                  Ok(_) => PasswordCheck::Valid,
+                  Err(_) => PasswordCheck::Invalid,
+            };
+      }
+
+      // Otherwise it is a plaintext password from before hashing was introduced
+      if bool::from(password.as_bytes().ct_eq(stored_password.as_bytes())) {
+            PasswordCheck::ValidLegacy
+      }
+      else {
+            PasswordCheck::Invalid
+      }
+}
+
+// Bytes of the HMAC kept in the receipt code
+static RECEIPT_MAC_LENGTH: usize = 16;
+
+fn get_receipt_mac(ballot_id: &str, candidate_id: &str) -> Option<Hmac<Sha256>> {
+      let receipt_secret: &str = get_config().election.receipt_secret.as_deref()?;
+      let mut mac = Hmac::<Sha256>::new_from_slice(receipt_secret.as_bytes()).ok()?;
+
+      mac.update(ballot_id.as_bytes());
+      mac.update(b"\n");
+      mac.update(candidate_id.as_bytes());
+
+      Some(mac)
+}
+
+/// `<ballot id>-<truncated HMAC of the ballot id and candidate>`, `None` if receipts are disabled.
+pub fn generate_receipt(ballot_id: &str, candidate_id: &str) -> Option<String> {
+      let mac_bytes = get_receipt_mac(ballot_id, candidate_id)?.finalize().into_bytes();
+
+      Some(format!("{}-{}", ballot_id, hex::encode(&mac_bytes[..RECEIPT_MAC_LENGTH])))
+}
+
+/// Split a receipt code into the ballot id and the HMAC.
+pub fn parse_receipt(receipt: &str) -> Option<(String, Vec<u8>)> {
+      let (ballot_id, mac) = receipt.rsplit_once('-')?;
+      let mac: Vec<u8> = hex::decode(mac).ok()?;
+
+      if ballot_id.is_empty() || mac.len() != RECEIPT_MAC_LENGTH {
+            return None;
+      }
+
+      Some((ballot_id.to_string(), mac))
+}
+
+pub fn verify_receipt(ballot_id: &str, candidate_id: &str, mac: &[u8]) -> bool {
+      match get_receipt_mac(ballot_id, candidate_id) {
+            Some(expected_mac) => expected_mac.verify_truncated_left(mac).is_ok(),
+            None => false,
       }
 }